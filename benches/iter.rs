@@ -0,0 +1,78 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rustyrocks::{Deserializable, KeyValueDB};
+use tempfile::tempdir;
+
+// Counts allocations made from Rust, to show how many each way of scanning
+// makes. rocksdb's own C++ allocations are not seen.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const ROWS: u64 = 1_000_000;
+
+fn allocations<R>(f: impl FnOnce() -> R) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+// The sum of the values, deserialized from rocksdb's own iterator, which
+// boxes every key and value.
+fn boxed_sum(db: &rocksdb::DB) -> u64 {
+    db.iterator(rocksdb::IteratorMode::Start)
+        .map(|kv| {
+            let (k, v) = kv.unwrap();
+            u64::deserialize(&k).unwrap();
+            u64::deserialize(&v).unwrap()
+        })
+        .sum()
+}
+
+fn scan(c: &mut Criterion) {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("db");
+    {
+        let db: KeyValueDB<u64, u64, u64> = KeyValueDB::open_default(&path).unwrap();
+        for start in (0..ROWS).step_by(10_000) {
+            db.try_extend((start..start + 10_000).map(|i| (i, i)))
+                .unwrap();
+        }
+        db.flush().unwrap();
+    }
+
+    let mut group = c.benchmark_group("scan/1M rows");
+    group.sample_size(10);
+    {
+        let db = rocksdb::DB::open_default(&path).unwrap();
+        eprintln!(
+            "rocksdb iterator: {} allocations",
+            allocations(|| boxed_sum(&db))
+        );
+        group.bench_function("rocksdb iterator", |b| b.iter(|| boxed_sum(&db)));
+    }
+    let db: KeyValueDB<u64, u64, u64> = KeyValueDB::open_default(&path).unwrap();
+    let sum = || db.db_values().map(Result::unwrap).sum::<u64>();
+    eprintln!("DBIter: {} allocations", allocations(sum));
+    group.bench_function("DBIter", |b| b.iter(sum));
+    group.finish();
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
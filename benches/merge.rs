@@ -0,0 +1,39 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rustyrocks::{AssociateMergeable, MergeableDB, SortedStringList};
+use tempfile::tempdir;
+
+fn big_list() -> SortedStringList {
+    (0..10_000).map(|i| format!("item-{:05}", i)).collect()
+}
+
+fn into_bytes(c: &mut Criterion) {
+    let list = big_list();
+    c.bench_function("into_bytes/10k strings", |b| {
+        b.iter_batched(
+            || list.clone(),
+            AssociateMergeable::into_bytes,
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn merge_then_get(c: &mut Criterion) {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, SortedStringList, &SortedStringList> =
+        MergeableDB::new(dir.path().join("db")).unwrap();
+    let list = big_list();
+    let one = SortedStringList::single("extra");
+    c.bench_function("merge+get/10k strings", |b| {
+        b.iter_batched(
+            || db.put("k", &list).unwrap(),
+            |()| {
+                db.merge("k", &one).unwrap();
+                db.get("k").unwrap().unwrap()
+            },
+            BatchSize::PerIteration,
+        )
+    });
+}
+
+criterion_group!(benches, into_bytes, merge_then_get);
+criterion_main!(benches);
@@ -0,0 +1,26 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use rustyrocks::KeyValueDB;
+use tempfile::tempdir;
+
+const ROWS: u32 = 100_000;
+
+fn full_scan(c: &mut Criterion) {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    let value = "x".repeat(100);
+    for i in 0..ROWS {
+        db.put(i, &value).unwrap();
+    }
+    db.flush().unwrap();
+
+    let mut group = c.benchmark_group("full scan/100k rows");
+    group.bench_function("db_iter", |b| b.iter(|| db.db_iter().count()));
+    group.bench_function("db_iter_scan 2MiB", |b| {
+        b.iter(|| db.db_iter_scan(2 << 20).count())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, full_scan);
+criterion_main!(benches);
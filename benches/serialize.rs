@@ -0,0 +1,31 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rustyrocks::Serializable;
+
+const KEYS: u32 = 10_000;
+
+fn tuples(c: &mut Criterion) {
+    let keys: Vec<(u32, i64)> = (0..KEYS).map(|i| (i, -(i as i64))).collect();
+
+    let mut group = c.benchmark_group("serialize/10k (u32, i64)");
+    group.bench_function("serialize", |b| {
+        b.iter(|| {
+            for &key in &keys {
+                black_box(key.serialize());
+            }
+        })
+    });
+    group.bench_function("serialize_into", |b| {
+        let mut buf = Vec::new();
+        b.iter(|| {
+            for &key in &keys {
+                buf.clear();
+                key.serialize_into(&mut buf);
+                black_box(&buf);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, tuples);
+criterion_main!(benches);
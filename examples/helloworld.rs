@@ -0,0 +1,97 @@
+//! Reads `word value` lines from stdin, collecting the set of values seen for
+//! each word, then prints every word with its values.
+
+use std::collections::BTreeSet;
+use std::io;
+use std::io::prelude::*;
+
+use rmp::decode::read_str_from_slice;
+use rmp::encode::{write_str, ValueWriteError};
+use rustyrocks::{AssociateMergeable, Deserializable, MergePolicy, MergeableDB, TrySerializable};
+use thiserror::Error;
+
+#[derive(Debug, Default)]
+pub struct BSet<T>(pub BTreeSet<T>);
+
+#[derive(Debug, Error)]
+#[error("corrupt set encoding")]
+pub struct CorruptSet;
+
+impl TrySerializable for &BSet<String> {
+    type Bytes = Vec<u8>;
+    type Error = ValueWriteError;
+
+    fn try_serialize(self) -> Result<Vec<u8>, ValueWriteError> {
+        let mut bytes = vec![];
+        for s in &self.0 {
+            write_str(&mut bytes, s)?;
+        }
+        Ok(bytes)
+    }
+}
+
+impl Deserializable for BSet<String> {
+    type Error = CorruptSet;
+
+    fn deserialize(mut bytes: &[u8]) -> Result<Self, CorruptSet> {
+        let mut set = BTreeSet::new();
+        while !bytes.is_empty() {
+            let (s, tail) = read_str_from_slice(bytes).map_err(|_| CorruptSet)?;
+            set.insert(s.to_owned());
+            bytes = tail;
+        }
+        Ok(BSet(set))
+    }
+}
+
+impl AssociateMergeable for BSet<String> {
+    fn merge(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        (&self)
+            .try_serialize()
+            .expect("writing to a Vec cannot fail")
+    }
+
+    fn handle_deser_error(_key: &[u8], _bytes: &[u8], _err: CorruptSet) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
+
+fn main() {
+    let db: MergeableDB<&str, BSet<String>, &BSet<String>> = MergeableDB::new("words.db").unwrap();
+    db.set_error_handler(|e| {
+        eprintln!(
+            "Skipping value for {}: {}",
+            String::from_utf8_lossy(e.key),
+            e.error
+        )
+    });
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line.unwrap();
+        let mut splits = line.trim().splitn(2, ' ');
+        let (word, value) = match (splits.next(), splits.next()) {
+            (Some(word), Some(value)) => (word, value),
+            _ => {
+                println!("Could not split '{}'", line);
+                continue;
+            }
+        };
+
+        let single = BSet(std::iter::once(value.to_owned()).collect());
+        db.merge_owned(word, single).unwrap();
+    }
+
+    for kv in db.db_iter() {
+        let (word, values) = kv.unwrap();
+        print!("{}:", word);
+        for v in values.0 {
+            print!(" {}", v);
+        }
+        println!();
+    }
+}
@@ -0,0 +1,76 @@
+//! An append-only log of values keyed by sequence number.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::{
+    deserialize_key, DBIter, Deserializable, KeyValueDB, RustyRocksError, TrySerializable,
+};
+
+/// A log of values under consecutive `u64` sequence numbers, starting from 0.
+///
+/// The first `append` reads the largest sequence number, and later ones count
+/// on from it, so there must be only one writer. Appends through one
+/// `AppendLog` are serialized with a lock, but two handles to the same
+/// database, in one process or several, can assign the same number and
+/// overwrite each other.
+pub struct AppendLog<V, VRef> {
+    kv: KeyValueDB<u64, V, VRef>,
+    // The next sequence number, once an append has looked it up.
+    append_lock: Mutex<Option<u64>>,
+}
+
+impl<V, VRef> AppendLog<V, VRef>
+where
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    /// Open the log at `path`, creating it if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        Ok(AppendLog {
+            kv: KeyValueDB::open_default(path)?,
+            append_lock: Mutex::new(None),
+        })
+    }
+
+    /// Add `v` to the end of the log, returning its sequence number.
+    ///
+    /// This fails with `RustyRocksError::LogFull` once the log holds an entry
+    /// at `u64::MAX`.
+    pub fn append(&self, v: VRef) -> Result<u64, RustyRocksError> {
+        let mut next = self.append_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = match *next {
+            Some(seq) => seq,
+            None => self.next_seq()?,
+        };
+        self.kv.put(seq, v)?;
+        // A full log is left for the next append to look up and report.
+        *next = seq.checked_add(1);
+        Ok(seq)
+    }
+
+    // The sequence number after the largest one in the log. Only the key is
+    // read, so an entry that fails to deserialize does not stop appends.
+    fn next_seq(&self) -> Result<u64, RustyRocksError> {
+        let mut iter = self.kv.db.raw_iterator();
+        iter.seek_to_last();
+        match iter.key() {
+            Some(key) => deserialize_key::<u64>(key)?
+                .checked_add(1)
+                .ok_or(RustyRocksError::LogFull),
+            None => {
+                iter.status()?;
+                Ok(0)
+            }
+        }
+    }
+
+    pub fn get(&self, seq: u64) -> Result<Option<V>, RustyRocksError> {
+        self.kv.get(seq)
+    }
+
+    /// Iterate over the entries in the order they were appended.
+    pub fn db_iter(&self) -> DBIter<'_, u64, V> {
+        self.kv.db_iter()
+    }
+}
@@ -0,0 +1,97 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use rocksdb::DB;
+
+use crate::{deserialize, serialize, Deserializable, KeyRef, RustyRocksError, TrySerializable};
+
+/// An async handle to a `KeyValueDB`, created with `KeyValueDB::to_async`.
+///
+/// Each call runs the blocking rocksdb operation on tokio's blocking thread
+/// pool, so it does not stall the executor. Keys and values are serialized
+/// and deserialized on the calling task; only bytes cross to the pool.
+pub struct AsyncKeyValueDB<KRef, V, VRef, K = <KRef as KeyRef>::Owned> {
+    db: Arc<DB>,
+    read_only: bool,
+    phantom: PhantomData<(KRef, V, VRef, K)>,
+}
+
+impl<KRef, V, VRef, K> Clone for AsyncKeyValueDB<KRef, V, VRef, K> {
+    fn clone(&self) -> Self {
+        AsyncKeyValueDB::new(Arc::clone(&self.db), self.read_only)
+    }
+}
+
+async fn blocking<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(r) => r,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+impl<KRef, V, VRef, K> AsyncKeyValueDB<KRef, V, VRef, K> {
+    pub(crate) fn new(db: Arc<DB>, read_only: bool) -> Self {
+        AsyncKeyValueDB {
+            db,
+            read_only,
+            phantom: PhantomData,
+        }
+    }
+
+    fn check_writable(&self) -> Result<(), RustyRocksError> {
+        if self.read_only {
+            return Err(RustyRocksError::ReadOnly);
+        }
+        Ok(())
+    }
+}
+
+impl<KRef, V, VRef, K> AsyncKeyValueDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    pub async fn get(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        let key = serialize(k)?.as_ref().to_vec();
+        let db = Arc::clone(&self.db);
+        match blocking(move || db.get(key)).await? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn put(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        let key = serialize(k)?.as_ref().to_vec();
+        let value = serialize(v)?.as_ref().to_vec();
+        let db = Arc::clone(&self.db);
+        blocking(move || db.put(key, value)).await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        let key = serialize(k)?.as_ref().to_vec();
+        let db = Arc::clone(&self.db);
+        blocking(move || db.delete(key)).await?;
+        Ok(())
+    }
+
+    /// Merge `v` into the value for `k`.
+    ///
+    /// This fails unless the database was opened with a merge operator, as
+    /// `MergeableDB` does.
+    pub async fn merge(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        let key = serialize(k)?.as_ref().to_vec();
+        let value = serialize(v)?.as_ref().to_vec();
+        let db = Arc::clone(&self.db);
+        blocking(move || db.merge(key, value)).await?;
+        Ok(())
+    }
+}
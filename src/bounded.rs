@@ -0,0 +1,122 @@
+//! Set values that keep only their largest items.
+
+use std::collections::BTreeSet;
+use std::iter::FromIterator;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{AssociateMergeable, Deserializable, MergePolicy, TrySerializable};
+
+/// A set holding at most the `N` largest items merged into it.
+///
+/// Merging unions the sets and then drops the smallest items until `N`
+/// remain. This happens inside the merge operator, so the cap holds however
+/// rocksdb groups operands during compaction, and keeping the largest `N`
+/// of a union gives the same result in any grouping. With timestamps or
+/// sequence numbers as the leading part of `T`, this keeps the most recent
+/// `N` items. Sets are stored with bincode, and a stored set with more than
+/// `N` items, such as one written with a larger bound, is trimmed as it is
+/// read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de> + Ord"),
+    from = "BTreeSet<T>"
+)]
+pub struct BoundedSet<T, const N: usize>(BTreeSet<T>);
+
+impl<T: Ord, const N: usize> BoundedSet<T, N> {
+    pub fn new() -> Self {
+        BoundedSet(BTreeSet::new())
+    }
+
+    /// A set of just `item`, to merge into a key.
+    pub fn single(item: T) -> Self {
+        std::iter::once(item).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.0.contains(item)
+    }
+
+    /// The items, smallest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.0.iter()
+    }
+
+    pub fn into_inner(self) -> BTreeSet<T> {
+        self.0
+    }
+
+    fn trim(&mut self) {
+        while self.0.len() > N {
+            self.0.pop_first();
+        }
+    }
+}
+
+impl<T: Ord, const N: usize> Default for BoundedSet<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord, const N: usize> FromIterator<T> for BoundedSet<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().collect::<BTreeSet<T>>().into()
+    }
+}
+
+/// Keeps the `N` largest items of the set.
+impl<T: Ord, const N: usize> From<BTreeSet<T>> for BoundedSet<T, N> {
+    fn from(set: BTreeSet<T>) -> Self {
+        let mut set = BoundedSet(set);
+        set.trim();
+        set
+    }
+}
+
+impl<T: Serialize, const N: usize> TrySerializable for &BoundedSet<T, N> {
+    type Bytes = Vec<u8>;
+    type Error = bincode::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+}
+
+impl<T: DeserializeOwned + Ord, const N: usize> Deserializable for BoundedSet<T, N> {
+    type Error = bincode::Error;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
+
+impl<T, const N: usize> AssociateMergeable for BoundedSet<T, N>
+where
+    T: Serialize + DeserializeOwned + Ord,
+{
+    fn merge(&mut self, other: Self) {
+        self.0.extend(other.0);
+        self.trim();
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        (&self)
+            .try_serialize()
+            .expect("bincode can encode any serde value")
+    }
+
+    fn handle_deser_error(_key: &[u8], _bytes: &[u8], _err: bincode::Error) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
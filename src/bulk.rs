@@ -0,0 +1,84 @@
+use std::marker::PhantomData;
+
+use rocksdb::{WriteBatch, WriteOptions, DB};
+
+use crate::{serialize, RustyRocksError, TrySerializable};
+
+/// Writes a large number of key-value pairs in batches of a fixed size.
+///
+/// Created by `KeyValueDB::bulk_loader`. Anything still buffered when the
+/// loader is dropped without calling `finish` is discarded.
+pub struct BulkLoader<'a, KRef, VRef> {
+    db: &'a DB,
+    batch: WriteBatch,
+    batch_size: usize,
+    written: u64,
+    disable_wal: bool,
+    compact_on_finish: bool,
+    phantom: PhantomData<(KRef, VRef)>,
+}
+
+impl<'a, KRef, VRef> BulkLoader<'a, KRef, VRef>
+where
+    KRef: TrySerializable,
+    VRef: TrySerializable,
+{
+    pub(crate) fn new(db: &'a DB, batch_size: usize) -> Self {
+        BulkLoader {
+            db,
+            batch: WriteBatch::default(),
+            batch_size: batch_size.max(1),
+            written: 0,
+            disable_wal: false,
+            compact_on_finish: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Skip the write-ahead log while loading, which is considerably faster.
+    ///
+    /// Rows written so far are only durable once `finish` has flushed them,
+    /// so a crash mid-load can lose any of them.
+    pub fn set_disable_wal(&mut self, disable: bool) {
+        self.disable_wal = disable;
+    }
+
+    /// Compact the whole database once loading has finished.
+    pub fn set_compact_on_finish(&mut self, compact: bool) {
+        self.compact_on_finish = compact;
+    }
+
+    /// Buffer a write, committing the buffer if it has reached the batch size.
+    pub fn put(&mut self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.batch.put(serialize(k)?, serialize(v)?);
+        if self.batch.len() >= self.batch_size {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), RustyRocksError> {
+        let batch = std::mem::take(&mut self.batch);
+        let rows = batch.len() as u64;
+        let mut opts = WriteOptions::default();
+        opts.disable_wal(self.disable_wal);
+        self.db.write_opt(batch, &opts)?;
+        self.written += rows;
+        Ok(())
+    }
+
+    /// Commit whatever is still buffered, and return the total number of rows
+    /// written.
+    pub fn finish(mut self) -> Result<u64, RustyRocksError> {
+        if !self.batch.is_empty() {
+            self.commit()?;
+        }
+        if self.disable_wal {
+            self.db.flush()?;
+        }
+        if self.compact_on_finish {
+            self.db.compact_range::<&[u8], &[u8]>(None, None);
+        }
+        Ok(self.written)
+    }
+}
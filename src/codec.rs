@@ -0,0 +1,239 @@
+//! Encodings for any type that implements serde's traits.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Deserializable, KeyRef, TrySerializable};
+
+/// An on-disk format for values of type `T`.
+///
+/// Codecs are chosen at the type level, through `Encoded<T, C>`, so they
+/// have no state of their own.
+pub trait Codec<T> {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    fn encode(value: &T) -> Result<Vec<u8>, Self::Error>;
+
+    fn decode(bytes: &[u8]) -> Result<T, Self::Error>;
+}
+
+/// Encodes values as JSON. This needs the `json` feature, which is on by
+/// default.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct JsonCodec;
+
+#[cfg(feature = "json")]
+impl<T: Serialize + DeserializeOwned> Codec<T> for JsonCodec {
+    type Error = serde_json::Error;
+
+    fn encode(value: &T) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(value)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(bytes)
+    }
+}
+
+/// Encodes values as MessagePack.
+///
+/// Structs are written as arrays of their fields, which is compact but means
+/// fields cannot be reordered once data has been stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MsgPackCodec;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for MsgPackCodec {
+    type Error = MsgPackError;
+
+    fn encode(value: &T) -> Result<Vec<u8>, MsgPackError> {
+        rmp_serde::to_vec(value).map_err(MsgPackError::Encode)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T, MsgPackError> {
+        rmp_serde::from_slice(bytes).map_err(MsgPackError::Decode)
+    }
+}
+
+/// A failure to encode or decode MessagePack.
+#[derive(Debug, thiserror::Error)]
+pub enum MsgPackError {
+    #[error(transparent)]
+    Encode(rmp_serde::encode::Error),
+    #[error(transparent)]
+    Decode(rmp_serde::decode::Error),
+}
+
+/// Encodes values with bincode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BincodeCodec;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for BincodeCodec {
+    type Error = bincode::Error;
+
+    fn encode(value: &T) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(value)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
+
+/// Stores a `T` in the format given by the codec `C`.
+///
+/// For example, `KeyValueDB<&str, Encoded<Config, JsonCodec>, &Encoded<Config,
+/// JsonCodec>>` stores its values as JSON.
+pub struct Encoded<T, C> {
+    pub value: T,
+    codec: PhantomData<fn() -> C>,
+}
+
+impl<T, C> Encoded<T, C> {
+    pub fn new(value: T) -> Self {
+        Encoded {
+            value,
+            codec: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, C> From<T> for Encoded<T, C> {
+    fn from(value: T) -> Self {
+        Encoded::new(value)
+    }
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for Encoded<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Encoded").field(&self.value).finish()
+    }
+}
+
+impl<T: Clone, C> Clone for Encoded<T, C> {
+    fn clone(&self) -> Self {
+        Encoded::new(self.value.clone())
+    }
+}
+
+impl<T: PartialEq, C> PartialEq for Encoded<T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq, C> Eq for Encoded<T, C> {}
+
+impl<T, C: Codec<T>> TrySerializable for &Encoded<T, C> {
+    type Bytes = Vec<u8>;
+    type Error = C::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, C::Error> {
+        C::encode(&self.value)
+    }
+}
+
+impl<T, C: Codec<T>> TrySerializable for Encoded<T, C> {
+    type Bytes = Vec<u8>;
+    type Error = C::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, C::Error> {
+        C::encode(&self.value)
+    }
+}
+
+impl<T, C: Codec<T>> KeyRef for Encoded<T, C> {
+    type Owned = Self;
+}
+
+impl<T, C: Codec<T>> KeyRef for &Encoded<T, C> {
+    type Owned = Encoded<T, C>;
+}
+
+impl<T, C: Codec<T>> Deserializable for Encoded<T, C> {
+    type Error = C::Error;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, C::Error> {
+        C::decode(bytes).map(Encoded::new)
+    }
+}
+
+/// Stores a `T` encoded with bincode.
+///
+/// This saves writing `Serializable` and `Deserializable` by hand for types
+/// that already derive `Serialize` and `Deserialize`. Bincode's encoding does
+/// not preserve order, so it is best suited to values rather than keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bincode<T>(pub T);
+
+impl<T: Serialize> TrySerializable for &Bincode<T> {
+    type Bytes = Vec<u8>;
+    type Error = bincode::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(&self.0)
+    }
+}
+
+impl<T: Serialize> TrySerializable for Bincode<T> {
+    type Bytes = Vec<u8>;
+    type Error = bincode::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, bincode::Error> {
+        (&self).try_serialize()
+    }
+}
+
+impl<T: DeserializeOwned> KeyRef for Bincode<T> {
+    type Owned = Self;
+}
+
+impl<T: DeserializeOwned> KeyRef for &Bincode<T> {
+    type Owned = Bincode<T>;
+}
+
+impl<T: DeserializeOwned> Deserializable for Bincode<T> {
+    type Error = bincode::Error;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes).map(Bincode)
+    }
+}
+
+// Untyped JSON, for tools that inspect values without knowing their types.
+
+#[cfg(feature = "json")]
+impl TrySerializable for &serde_json::Value {
+    type Bytes = Vec<u8>;
+    type Error = serde_json::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(self)
+    }
+}
+
+#[cfg(feature = "json")]
+impl TrySerializable for serde_json::Value {
+    type Bytes = Vec<u8>;
+    type Error = serde_json::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, serde_json::Error> {
+        (&self).try_serialize()
+    }
+}
+
+#[cfg(feature = "json")]
+impl Deserializable for serde_json::Value {
+    type Error = serde_json::Error;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(bytes)
+    }
+}
@@ -0,0 +1,308 @@
+//! Access to a database's column families by name.
+//!
+//! Every column family of a `KeyValueDB` holds the same key and value types.
+
+use std::path::Path;
+
+use rocksdb::{
+    ColumnFamily, ColumnFamilyDescriptor, DBCompressionType, Direction, IteratorMode, Options,
+    WriteBatch, DB, DEFAULT_COLUMN_FAMILY_NAME,
+};
+
+use crate::{
+    deserialize, serialize, DBIter, Deserializable, KeyValueDB, RustyRocksError, TrySerializable,
+};
+
+impl<KRef, V, VRef, K> KeyValueDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    /// Open the database at `path` with the column families named in `cfs`,
+    /// creating the database and any missing column families.
+    ///
+    /// The default column family is always open, whether or not it is named.
+    pub fn open_cf<P, I, N>(path: P, cfs: I) -> Result<Self, RustyRocksError>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = N>,
+        N: AsRef<str>,
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        Self::open_cf_with_opts(path, opts, cfs)
+    }
+
+    /// Like `open_cf`, with `opts` for the database and every column family,
+    /// which must ask for them to be created if they may not exist.
+    ///
+    /// Set `Options::set_atomic_flush` here when writes to several column
+    /// families must survive a crash together with the write-ahead log
+    /// disabled.
+    pub fn open_cf_with_opts<P, I, N>(
+        path: P,
+        opts: Options,
+        cfs: I,
+    ) -> Result<Self, RustyRocksError>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = N>,
+        N: AsRef<str>,
+    {
+        let path = path.as_ref();
+        // rocksdb gives the default column family default options unless it
+        // is listed.
+        let mut names: Vec<String> = cfs.into_iter().map(|n| n.as_ref().to_owned()).collect();
+        if !names.iter().any(|n| n == DEFAULT_COLUMN_FAMILY_NAME) {
+            names.push(DEFAULT_COLUMN_FAMILY_NAME.to_owned());
+        }
+        let cfs = names
+            .into_iter()
+            .map(|name| ColumnFamilyDescriptor::new(name, opts.clone()));
+        let db = DB::open_cf_descriptors(&opts, path, cfs)?;
+        Self::from_cf_db(db, &opts, path)
+    }
+
+    /// Like `open_cf`, with options given separately for each column family.
+    ///
+    /// `ColumnFamilyBuilder` converts into a descriptor, so `cfs` can be a
+    /// list of builders.
+    pub fn open_cf_descriptors<P, I, D>(path: P, cfs: I) -> Result<Self, RustyRocksError>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = D>,
+        D: Into<ColumnFamilyDescriptor>,
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let path = path.as_ref();
+        let cfs = cfs.into_iter().map(Into::into);
+        let db = DB::open_cf_descriptors(&opts, path, cfs)?;
+        Self::from_cf_db(db, &opts, path)
+    }
+
+    /// The names of the column families in the database at `path`, including
+    /// `default`, to pass to `open_cf` when reopening a database created
+    /// elsewhere.
+    pub fn list_cf<P: AsRef<Path>>(path: P) -> Result<Vec<String>, RustyRocksError> {
+        Ok(DB::list_cf(&Options::default(), path)?)
+    }
+
+    fn from_cf_db(db: DB, opts: &Options, path: &Path) -> Result<Self, RustyRocksError> {
+        let mut kv = Self::from_db(db, false);
+        kv.column_families = DB::list_cf(opts, path)?;
+        Ok(kv)
+    }
+
+    pub(crate) fn cf_handle(&self, name: &str) -> Result<&ColumnFamily, RustyRocksError> {
+        self.db
+            .cf_handle(name)
+            .ok_or_else(|| RustyRocksError::UnknownColumnFamily(name.to_owned()))
+    }
+
+    pub fn get_cf(&self, cf: &str, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        match self.db.get_pinned_cf(self.cf_handle(cf)?, serialize(k)?)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Like `with_value`, reading from the column family `cf`.
+    pub fn with_value_cf<F, R>(&self, cf: &str, k: KRef, f: F) -> Result<Option<R>, RustyRocksError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        Ok(self
+            .db
+            .get_pinned_cf(self.cf_handle(cf)?, serialize(k)?)?
+            .map(|bytes| f(&bytes)))
+    }
+
+    pub fn put_cf(&self, cf: &str, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        self.db
+            .put_cf(self.cf_handle(cf)?, serialize(k)?, serialize(v)?)?;
+        Ok(())
+    }
+
+    pub fn delete_cf(&self, cf: &str, k: KRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        self.db.delete_cf(self.cf_handle(cf)?, serialize(k)?)?;
+        Ok(())
+    }
+
+    /// Apply every `(column family, operation)` pair in `ops` in a single
+    /// atomic write, so that either all of them are visible or none are.
+    ///
+    /// Nothing is written if any column family is unknown or any key or value
+    /// fails to serialize.
+    pub fn write_batch_cf<'c, I>(&self, ops: I) -> Result<(), RustyRocksError>
+    where
+        I: IntoIterator<Item = (&'c str, BatchOp<KRef, VRef>)>,
+    {
+        self.check_writable()?;
+        let mut batch = WriteBatch::default();
+        for (cf, op) in ops {
+            let cf = self.cf_handle(cf)?;
+            match op {
+                BatchOp::Put(k, v) => batch.put_cf(cf, serialize(k)?, serialize(v)?),
+                BatchOp::Delete(k) => batch.delete_cf(cf, serialize(k)?),
+            }
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Iterate over the key-value pairs in the column family `cf`, in key
+    /// order.
+    ///
+    /// Unlike `db_iter`, the two ends of the iterator do not read from a
+    /// shared snapshot, so a write made while it is being created can be seen
+    /// from one end and not the other.
+    pub fn db_iter_cf(&self, cf: &str) -> Result<DBIter<'_, K, V>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        let cf = self.cf_handle(cf)?;
+        Ok(DBIter::configured_cf(
+            &self.db,
+            cf,
+            IteratorMode::Start,
+            |_| {},
+        ))
+    }
+
+    /// Like `db_iter_cf`, in reverse key order.
+    pub fn db_iter_cf_rev(&self, cf: &str) -> Result<DBIter<'_, K, V>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        let cf = self.cf_handle(cf)?;
+        Ok(DBIter::configured_cf(
+            &self.db,
+            cf,
+            IteratorMode::End,
+            |_| {},
+        ))
+    }
+
+    /// Iterate over the key-value pairs in the column family `cf` from `start`
+    /// up to but not including `end`, in key order.
+    pub fn db_iter_cf_range(
+        &self,
+        cf: &str,
+        start: KRef,
+        end: KRef,
+    ) -> Result<DBIter<'_, K, V>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        let cf = self.cf_handle(cf)?;
+        let start = serialize(start)?;
+        let end = serialize(end)?;
+        let end = end.as_ref();
+        Ok(DBIter::configured_cf(
+            &self.db,
+            cf,
+            IteratorMode::From(start.as_ref(), Direction::Forward),
+            |opts| opts.set_iterate_upper_bound(end),
+        ))
+    }
+
+    /// Like `with_value_cf` for each of `keys` in turn, reading them from
+    /// `cf` in one batch, in the same order.
+    ///
+    /// The values stay pinned in rocksdb rather than being copied out, so a
+    /// closure that only looks at the bytes reads the whole batch without
+    /// allocating per value.
+    pub fn multi_with_values_cf<I, F, R>(
+        &self,
+        cf: &str,
+        keys: I,
+        mut f: F,
+    ) -> Result<Vec<Option<R>>, RustyRocksError>
+    where
+        I: IntoIterator<Item = KRef>,
+        F: FnMut(&[u8]) -> R,
+    {
+        let keys = keys
+            .into_iter()
+            .map(serialize)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.db
+            .batched_multi_get_cf(self.cf_handle(cf)?, &keys, false)
+            .into_iter()
+            .map(|value| Ok(value?.map(|bytes| f(&bytes))))
+            .collect()
+    }
+
+    /// Read the value for each `(column family, key)` pair in one batched
+    /// call, returning the values in the same order.
+    pub fn multi_get_cf<'c, I>(&self, keys: I) -> Result<Vec<Option<V>>, RustyRocksError>
+    where
+        I: IntoIterator<Item = (&'c str, KRef)>,
+    {
+        let keys = keys
+            .into_iter()
+            .map(|(cf, k)| Ok((self.cf_handle(cf)?, serialize(k)?)))
+            .collect::<Result<Vec<_>, RustyRocksError>>()?;
+        self.db
+            .multi_get_cf(keys.iter().map(|(cf, k)| (*cf, k)))
+            .into_iter()
+            .map(|value| match value? {
+                Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+                None => Ok(None),
+            })
+            .collect()
+    }
+}
+
+/// Options for one column family, for `KeyValueDB::open_cf_descriptors`.
+pub struct ColumnFamilyBuilder {
+    name: String,
+    opts: Options,
+}
+
+impl ColumnFamilyBuilder {
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        ColumnFamilyBuilder {
+            name: name.into(),
+            opts: Options::default(),
+        }
+    }
+
+    /// Compress this column family's SST files with `compression`.
+    ///
+    /// The compression library must have been built into rocksdb, or opening
+    /// the database fails.
+    pub fn compression(mut self, compression: DBCompressionType) -> Self {
+        self.opts.set_compression_type(compression);
+        self
+    }
+
+    /// Apply `configure` to the column family's options, for settings with no
+    /// method of their own.
+    pub fn configure<F: FnOnce(&mut Options)>(mut self, configure: F) -> Self {
+        configure(&mut self.opts);
+        self
+    }
+
+    pub fn build(self) -> ColumnFamilyDescriptor {
+        ColumnFamilyDescriptor::new(self.name, self.opts)
+    }
+}
+
+impl From<ColumnFamilyBuilder> for ColumnFamilyDescriptor {
+    fn from(builder: ColumnFamilyBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// A write to one column family, for `KeyValueDB::write_batch_cf`.
+pub enum BatchOp<KRef, VRef> {
+    Put(KRef, VRef),
+    Delete(KRef),
+}
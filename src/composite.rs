@@ -0,0 +1,118 @@
+//! Keys made of several byte string components.
+
+use std::iter::FromIterator;
+
+use crate::{CompositeKeyError, Deserializable, KeyRef, Serializable};
+
+/// A key made of a sequence of byte string components, such as
+/// `["user", "123", "posts"]`.
+///
+/// Joining components with a separator is ambiguous once a component can
+/// contain the separator. Instead, each component is written with its zero
+/// bytes escaped as `00 ff` and is terminated by `00 01`. This keeps keys
+/// distinct and sorts them component by component, each component in byte
+/// order, with a key sorting before any longer key it is a prefix of. A
+/// composite key is therefore also usable as a prefix for `db_iter_prefix`,
+/// matching every key that extends it with more components.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct CompositeKey {
+    components: Vec<Vec<u8>>,
+}
+
+impl CompositeKey {
+    pub fn new() -> Self {
+        CompositeKey::default()
+    }
+
+    /// Add `component` to the end of the key.
+    pub fn push<C: AsRef<[u8]>>(mut self, component: C) -> Self {
+        self.components.push(component.as_ref().to_vec());
+        self
+    }
+
+    pub fn components(&self) -> &[Vec<u8>] {
+        &self.components
+    }
+
+    pub fn into_components(self) -> Vec<Vec<u8>> {
+        self.components
+    }
+}
+
+impl<C: AsRef<[u8]>> FromIterator<C> for CompositeKey {
+    fn from_iter<I: IntoIterator<Item = C>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(CompositeKey::new(), CompositeKey::push)
+    }
+}
+
+impl KeyRef for CompositeKey {
+    type Owned = CompositeKey;
+}
+
+impl KeyRef for &CompositeKey {
+    type Owned = CompositeKey;
+}
+
+impl Serializable for &CompositeKey {
+    type Bytes = Vec<u8>;
+
+    fn serialize(self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.serialize_into(&mut bytes);
+        bytes
+    }
+
+    fn serialize_into(self, buf: &mut Vec<u8>) {
+        buf.reserve(self.components.iter().map(|c| c.len() + 2).sum());
+        for component in &self.components {
+            for &b in component {
+                buf.push(b);
+                if b == 0 {
+                    buf.push(0xff);
+                }
+            }
+            buf.extend_from_slice(&[0, 1]);
+        }
+    }
+}
+
+impl Serializable for CompositeKey {
+    type Bytes = Vec<u8>;
+
+    fn serialize(self) -> Vec<u8> {
+        (&self).serialize()
+    }
+
+    fn serialize_into(self, buf: &mut Vec<u8>) {
+        (&self).serialize_into(buf)
+    }
+}
+
+impl Deserializable for CompositeKey {
+    type Error = CompositeKeyError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, CompositeKeyError> {
+        let malformed = || CompositeKeyError {
+            bytes: bytes.to_vec(),
+        };
+        let mut components = Vec::new();
+        let mut component = Vec::new();
+        let mut iter = bytes.iter();
+        while let Some(&b) = iter.next() {
+            if b != 0 {
+                component.push(b);
+                continue;
+            }
+            match iter.next() {
+                Some(0xff) => component.push(0),
+                Some(1) => components.push(std::mem::take(&mut component)),
+                _ => return Err(malformed()),
+            }
+        }
+        if !component.is_empty() {
+            return Err(malformed());
+        }
+        Ok(CompositeKey { components })
+    }
+}
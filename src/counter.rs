@@ -0,0 +1,87 @@
+//! Counters kept with a summing merge operator.
+
+use std::path::Path;
+
+use crate::{
+    AssociateMergeable, Deserializable, KeyRef, LengthError, MergePolicy, MergeableDB,
+    RustyRocksError, Serializable, TrySerializable,
+};
+
+// A count stored as a big-endian `u64`, merged by adding.
+struct Count(u64);
+
+impl Serializable for Count {
+    type Bytes = [u8; 8];
+
+    fn serialize(self) -> [u8; 8] {
+        self.0.serialize()
+    }
+}
+
+impl Deserializable for Count {
+    type Error = LengthError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, LengthError> {
+        u64::deserialize(bytes).map(Count)
+    }
+}
+
+impl AssociateMergeable for Count {
+    fn merge(&mut self, other: Self) {
+        self.0 = self.0.saturating_add(other.0);
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.serialize().to_vec()
+    }
+
+    fn handle_deser_error(_key: &[u8], _bytes: &[u8], _err: LengthError) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
+
+/// A database of `u64` counters, where incrementing is a merge rather than a
+/// read followed by a write.
+///
+/// Increments from any number of threads are never lost, and cost no more
+/// than a write. Counts saturate at `u64::MAX`.
+pub struct CounterDB<KRef, K = <KRef as KeyRef>::Owned> {
+    db: MergeableDB<KRef, Count, Count, K>,
+}
+
+impl<KRef, K> CounterDB<KRef, K>
+where
+    KRef: TrySerializable,
+{
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        Ok(CounterDB {
+            db: MergeableDB::with_name(path, "rustyrocks counter")?,
+        })
+    }
+
+    /// Add `by` to the count for `k`.
+    pub fn incr(&self, k: KRef, by: u64) -> Result<(), RustyRocksError> {
+        self.db.merge(k, Count(by))
+    }
+
+    /// The count for `k`, which is 0 if it has never been incremented.
+    pub fn get_count(&self, k: KRef) -> Result<u64, RustyRocksError> {
+        Ok(self.db.get(k)?.map_or(0, |count| count.0))
+    }
+
+    /// Reset the count for `k` to 0.
+    pub fn reset(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.db.delete(k)
+    }
+
+    /// Iterate over every counter and its count, in key order.
+    pub fn counts(&self) -> impl Iterator<Item = Result<(K, u64), RustyRocksError>> + '_
+    where
+        K: Deserializable,
+    {
+        self.db
+            .db_iter()
+            .map(|kv| kv.map(|(k, count): (K, Count)| (k, count.0)))
+    }
+}
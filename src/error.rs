@@ -0,0 +1,140 @@
+use std::error::Error as StdError;
+
+use thiserror::Error;
+
+/// Anything that can go wrong reading from or writing to a typed database.
+#[derive(Debug, Error)]
+pub enum RustyRocksError {
+    #[error("rocksdb error: {0}")]
+    RocksDb(#[from] rocksdb::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to deserialize: {0}")]
+    Deserialize(#[source] Box<dyn StdError + Send + Sync + 'static>),
+    /// A key read while iterating failed to deserialize.
+    #[error("failed to deserialize key {}: {source}", preview(.bytes))]
+    KeyDeserialize {
+        bytes: Vec<u8>,
+        source: Box<dyn StdError + Send + Sync + 'static>,
+    },
+    /// A value read while iterating failed to deserialize.
+    #[error("failed to deserialize value for key {}: {source}", preview(.key))]
+    ValueDeserialize {
+        key: Vec<u8>,
+        bytes: Vec<u8>,
+        source: Box<dyn StdError + Send + Sync + 'static>,
+    },
+    #[error("failed to serialize: {0}")]
+    Serialize(#[source] Box<dyn StdError + Send + Sync + 'static>),
+    #[error("database is open read-only")]
+    ReadOnly,
+    /// A `MergeableDB` was opened with a different merge operator name from
+    /// the one its database was created with.
+    #[error("database uses merge operator {found:?}, but was opened with {expected:?}")]
+    MergeOperatorMismatch { expected: String, found: String },
+    /// A transaction gave up waiting for a lock held by another transaction.
+    #[error("timed out waiting for a lock")]
+    TimedOut,
+    /// Keys given to `KeyValueDB::checked_put_sorted` were out of order.
+    #[error("key {index} sorts before the key preceding it")]
+    KeysOutOfOrder { index: usize },
+    /// Data on disk failed its checksum or was otherwise unreadable.
+    #[error("corrupt data: {0}")]
+    Corruption(#[source] rocksdb::Error),
+    /// A `TypedIndexedBatch` was read at a key it merges into, which cannot
+    /// be resolved until the batch is written.
+    #[error("cannot read a key with a merge staged in the batch")]
+    StagedMerge,
+    #[error("no column family named {0:?}")]
+    UnknownColumnFamily(String),
+    /// An `AppendLog` already holds an entry at the largest sequence number.
+    #[error("append log has no sequence numbers left")]
+    LogFull,
+}
+
+// Hex for error messages, truncated so a corrupt value cannot swamp them.
+fn preview(bytes: &[u8]) -> String {
+    const MAX: usize = 32;
+    let mut hex = String::from("0x");
+    for b in bytes.iter().take(MAX) {
+        hex.push_str(&format!("{:02x}", b));
+    }
+    if bytes.len() > MAX {
+        hex.push_str(&format!("... ({} bytes)", bytes.len()));
+    }
+    hex
+}
+
+impl RustyRocksError {
+    pub fn deserialize<E: StdError + Send + Sync + 'static>(err: E) -> Self {
+        RustyRocksError::Deserialize(Box::new(err))
+    }
+
+    pub fn serialize<E: StdError + Send + Sync + 'static>(err: E) -> Self {
+        RustyRocksError::Serialize(Box::new(err))
+    }
+}
+
+/// Bytes of the wrong length for a fixed-width type.
+#[derive(Debug, Error)]
+#[error("expected {expected} bytes, found {found}")]
+pub struct LengthError {
+    pub expected: usize,
+    pub found: usize,
+}
+
+/// Bytes that failed to deserialize as a fixed-width value with invalid bit
+/// patterns, such as `bool` or `char`.
+#[derive(Debug, Error)]
+pub enum PrimitiveError {
+    #[error(transparent)]
+    Length(#[from] LengthError),
+    #[error("invalid {type_name} encoding {}", preview(.bytes))]
+    Invalid {
+        type_name: &'static str,
+        bytes: Vec<u8>,
+    },
+}
+
+/// Bytes that are not a valid `CompositeKey` encoding.
+#[derive(Debug, Error)]
+#[error("malformed composite key {}", preview(.bytes))]
+pub struct CompositeKeyError {
+    pub bytes: Vec<u8>,
+}
+
+/// Bytes that are not a valid `SortedStringList` encoding.
+#[derive(Debug, Error)]
+#[error("malformed string list {}", preview(.bytes))]
+pub struct StringListError {
+    pub bytes: Vec<u8>,
+}
+
+/// Bytes that are not a valid `FieldMap` encoding, or hold a field or value
+/// that failed to deserialize.
+#[derive(Debug, Error)]
+#[error("malformed field map {}", preview(.bytes))]
+pub struct FieldMapError {
+    pub bytes: Vec<u8>,
+}
+
+/// Bytes that failed to deserialize as a tuple of fixed-width components.
+#[derive(Debug, Error)]
+pub enum TupleError {
+    #[error(transparent)]
+    Length(#[from] LengthError),
+    #[error("component {index}: {source}")]
+    Component {
+        index: usize,
+        source: Box<dyn StdError + Send + Sync + 'static>,
+    },
+}
+
+/// Bytes that failed to deserialize as a `Versioned` value.
+#[derive(Debug, Error)]
+pub enum VersionedError<E> {
+    #[error("missing version tag")]
+    Untagged,
+    #[error(transparent)]
+    Value(E),
+}
@@ -0,0 +1,171 @@
+//! Maps of fields stored under a key, updated a field at a time by merging.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::Path;
+
+use rmp::decode::read_bin_len;
+use rmp::encode::write_bin;
+
+use crate::{
+    AssociateMergeable, Deserializable, FieldMapError, KeyRef, MergePolicy, MergeableDB,
+    RustyRocksError, Serializable, TrySerializable,
+};
+
+/// A map from fields to values, merged by setting each field of the later
+/// map in the earlier one, so the last value written for a field wins.
+///
+/// The fields and values are stored alternately as msgpack binaries, in no
+/// particular order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMap<F: Eq + Hash, V>(HashMap<F, V>);
+
+impl<F: Eq + Hash, V> FieldMap<F, V> {
+    pub fn new() -> Self {
+        FieldMap(HashMap::new())
+    }
+
+    /// A map of just `field` set to `value`, to merge into a key.
+    pub fn single(field: F, value: V) -> Self {
+        let mut map = HashMap::with_capacity(1);
+        map.insert(field, value);
+        FieldMap(map)
+    }
+
+    pub fn get(&self, field: &F) -> Option<&V> {
+        self.0.get(field)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_map(self) -> HashMap<F, V> {
+        self.0
+    }
+}
+
+impl<F: Eq + Hash, V> Default for FieldMap<F, V> {
+    fn default() -> Self {
+        FieldMap::new()
+    }
+}
+
+impl<F: Eq + Hash, V> From<HashMap<F, V>> for FieldMap<F, V> {
+    fn from(map: HashMap<F, V>) -> Self {
+        FieldMap(map)
+    }
+}
+
+impl<F, V> Serializable for FieldMap<F, V>
+where
+    F: Serializable + Eq + Hash,
+    V: Serializable,
+{
+    type Bytes = Vec<u8>;
+
+    fn serialize(self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (field, value) in self.0 {
+            write_bin(&mut bytes, field.serialize().as_ref())
+                .expect("writing to a Vec cannot fail");
+            write_bin(&mut bytes, value.serialize().as_ref())
+                .expect("writing to a Vec cannot fail");
+        }
+        bytes
+    }
+}
+
+// The next msgpack binary in `unparsed`, advancing past it.
+fn read_bin<'a>(unparsed: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = read_bin_len(unparsed).ok()? as usize;
+    if unparsed.len() < len {
+        return None;
+    }
+    let (bin, tail) = unparsed.split_at(len);
+    *unparsed = tail;
+    Some(bin)
+}
+
+impl<F, V> Deserializable for FieldMap<F, V>
+where
+    F: Deserializable + Eq + Hash,
+    V: Deserializable,
+{
+    type Error = FieldMapError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, FieldMapError> {
+        let malformed = || FieldMapError {
+            bytes: bytes.to_vec(),
+        };
+        let mut map = HashMap::new();
+        let mut unparsed = bytes;
+        while !unparsed.is_empty() {
+            let field = read_bin(&mut unparsed).ok_or_else(malformed)?;
+            let value = read_bin(&mut unparsed).ok_or_else(malformed)?;
+            map.insert(
+                F::deserialize(field).map_err(|_| malformed())?,
+                V::deserialize(value).map_err(|_| malformed())?,
+            );
+        }
+        Ok(FieldMap(map))
+    }
+}
+
+impl<F, V> AssociateMergeable for FieldMap<F, V>
+where
+    F: Serializable + Deserializable + Eq + Hash,
+    V: Serializable + Deserializable,
+{
+    fn merge(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.serialize()
+    }
+
+    fn handle_deser_error(_key: &[u8], _bytes: &[u8], _err: FieldMapError) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
+
+/// A database of `FieldMap`s, where setting a field is a merge rather than
+/// a read of the whole map followed by a write.
+pub struct FieldMapDB<KRef, F: Eq + Hash, V, K = <KRef as KeyRef>::Owned> {
+    db: MergeableDB<KRef, FieldMap<F, V>, FieldMap<F, V>, K>,
+}
+
+impl<KRef, F, V, K> FieldMapDB<KRef, F, V, K>
+where
+    KRef: TrySerializable,
+    F: Serializable + Deserializable + Eq + Hash + 'static,
+    V: Serializable + Deserializable + 'static,
+{
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        Ok(FieldMapDB {
+            db: MergeableDB::with_name(path, "rustyrocks field map")?,
+        })
+    }
+
+    /// Set `field` to `value` in the map for `k`, leaving its other fields
+    /// as they are.
+    pub fn set_field(&self, k: KRef, field: F, value: V) -> Result<(), RustyRocksError> {
+        self.db.merge(k, FieldMap::single(field, value))
+    }
+
+    /// The fields set in the map for `k`, which is empty if none have been.
+    pub fn get_map(&self, k: KRef) -> Result<HashMap<F, V>, RustyRocksError> {
+        Ok(self.db.get(k)?.map(FieldMap::into_map).unwrap_or_default())
+    }
+
+    /// Remove every field from the map for `k`.
+    pub fn clear(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.db.delete(k)
+    }
+}
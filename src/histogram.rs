@@ -0,0 +1,102 @@
+//! Histograms of bucketed counts kept with a merge operator.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::{
+    AssociateMergeable, Deserializable, KeyRef, LengthError, MergePolicy, MergeableDB,
+    RustyRocksError, Serializable, TrySerializable, TupleError,
+};
+
+// Counts per bucket, stored as a run of `(i64, u64)` tuples in bucket order
+// and merged by adding the counts of matching buckets.
+struct Histogram(BTreeMap<i64, u64>);
+
+const ENTRY_WIDTH: usize = 16;
+
+impl Serializable for Histogram {
+    type Bytes = Vec<u8>;
+
+    fn serialize(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.0.len() * ENTRY_WIDTH);
+        for entry in self.0 {
+            bytes.extend_from_slice(&entry.serialize());
+        }
+        bytes
+    }
+}
+
+impl Deserializable for Histogram {
+    type Error = TupleError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, TupleError> {
+        let entries = bytes.chunks_exact(ENTRY_WIDTH);
+        if !entries.remainder().is_empty() {
+            return Err(LengthError {
+                expected: bytes.len() - entries.remainder().len() + ENTRY_WIDTH,
+                found: bytes.len(),
+            }
+            .into());
+        }
+        entries
+            .map(<(i64, u64)>::deserialize)
+            .collect::<Result<_, _>>()
+            .map(Histogram)
+    }
+}
+
+impl AssociateMergeable for Histogram {
+    fn merge(&mut self, other: Self) {
+        for (bucket, count) in other.0 {
+            let total = self.0.entry(bucket).or_insert(0);
+            *total = total.saturating_add(count);
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.serialize()
+    }
+
+    fn handle_deser_error(_key: &[u8], _bytes: &[u8], _err: TupleError) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
+
+/// A database of histograms, counting observations per `i64` bucket under
+/// each key, where adding to a bucket is a merge rather than a read followed
+/// by a write.
+///
+/// Counts saturate at `u64::MAX`.
+pub struct HistogramDB<KRef, K = <KRef as KeyRef>::Owned> {
+    db: MergeableDB<KRef, Histogram, Histogram, K>,
+}
+
+impl<KRef, K> HistogramDB<KRef, K>
+where
+    KRef: TrySerializable,
+{
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        Ok(HistogramDB {
+            db: MergeableDB::with_name(path, "rustyrocks histogram")?,
+        })
+    }
+
+    /// Add `count` to `bucket` in the histogram for `k`.
+    pub fn add(&self, k: KRef, bucket: i64, count: u64) -> Result<(), RustyRocksError> {
+        let mut buckets = BTreeMap::new();
+        buckets.insert(bucket, count);
+        self.db.merge(k, Histogram(buckets))
+    }
+
+    /// The count in each bucket of the histogram for `k`, which is empty if
+    /// nothing has been added to it.
+    pub fn get_histogram(&self, k: KRef) -> Result<BTreeMap<i64, u64>, RustyRocksError> {
+        Ok(self.db.get(k)?.map(|h| h.0).unwrap_or_default())
+    }
+
+    /// Empty the histogram for `k`.
+    pub fn reset(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.db.delete(k)
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use rocksdb::{WriteBatch, DB};
+
+use crate::{deserialize, serialize, Deserializable, RustyRocksError, TrySerializable};
+
+// The last write the batch makes to a key.
+enum Staged {
+    Put(Vec<u8>),
+    Delete,
+    Merge,
+}
+
+/// A write batch whose writes can be read back before it is committed.
+///
+/// Created by `KeyValueDB::indexed_batch` and committed atomically by
+/// `KeyValueDB::write_indexed`. Reads see the batch's own writes layered over
+/// the database. The rocksdb crate does not wrap rocksdb's
+/// `WriteBatchWithIndex`, so the batch keeps its own index of the last write
+/// to each key alongside a plain `WriteBatch`.
+pub struct TypedIndexedBatch<'a, KRef, V, VRef> {
+    db: &'a DB,
+    batch: WriteBatch,
+    staged: BTreeMap<Vec<u8>, Staged>,
+    phantom: PhantomData<(KRef, V, VRef)>,
+}
+
+impl<'a, KRef, V, VRef> TypedIndexedBatch<'a, KRef, V, VRef>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    pub(crate) fn new(db: &'a DB) -> Self {
+        TypedIndexedBatch {
+            db,
+            batch: WriteBatch::default(),
+            staged: BTreeMap::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Read the value `k` will have once the batch is committed, if nothing
+    /// else writes it first.
+    ///
+    /// Only rocksdb can apply a merge operator, so this fails with
+    /// `RustyRocksError::StagedMerge` if the batch merges into `k`.
+    pub fn get(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        let key = serialize(k)?;
+        match self.staged.get(key.as_ref()) {
+            Some(Staged::Put(bytes)) => Ok(Some(deserialize(bytes)?)),
+            Some(Staged::Delete) => Ok(None),
+            Some(Staged::Merge) => Err(RustyRocksError::StagedMerge),
+            None => match self.db.get_pinned(key)? {
+                Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+                None => Ok(None),
+            },
+        }
+    }
+
+    pub fn put(&mut self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        let (key, value) = (serialize(k)?, serialize(v)?);
+        self.batch.put(&key, &value);
+        self.staged
+            .insert(key.as_ref().to_vec(), Staged::Put(value.as_ref().to_vec()));
+        Ok(())
+    }
+
+    pub fn delete(&mut self, k: KRef) -> Result<(), RustyRocksError> {
+        let key = serialize(k)?;
+        self.batch.delete(&key);
+        self.staged.insert(key.as_ref().to_vec(), Staged::Delete);
+        Ok(())
+    }
+
+    /// Merge `v` into the value for `k`, with the merge operator the database
+    /// was opened with.
+    pub fn merge(&mut self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        let key = serialize(k)?;
+        self.batch.merge(&key, serialize(v)?);
+        self.staged.insert(key.as_ref().to_vec(), Staged::Merge);
+        Ok(())
+    }
+
+    /// The number of writes in the batch.
+    pub fn len(&self) -> usize {
+        self.batch.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.batch.is_empty()
+    }
+
+    pub(crate) fn into_batch(self) -> WriteBatch {
+        self.batch
+    }
+}
@@ -0,0 +1,478 @@
+use std::any::type_name;
+use std::collections::VecDeque;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use rocksdb::{ColumnFamily, DBRawIterator, Direction, IteratorMode, ReadOptions, Snapshot, DB};
+
+use crate::{
+    deserialize_key, deserialize_pair, deserialize_value, Deserializable, RustyRocksError,
+};
+
+/// What a `DBIter` deserializes from each entry and yields.
+///
+/// `Pairs` yields key-value pairs, `KeysOnly` just keys and `ValuesOnly`
+/// just values. The half left out is never deserialized, so it need not even
+/// be `Deserializable`.
+pub trait Projection<K, V> {
+    type Item;
+
+    fn project(key: &[u8], value: &[u8]) -> Result<Self::Item, RustyRocksError>;
+}
+
+/// Yield `(K, V)` pairs from a `DBIter`. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pairs;
+
+/// Yield only the keys from a `DBIter`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeysOnly;
+
+/// Yield only the values from a `DBIter`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValuesOnly;
+
+impl<K: Deserializable, V: Deserializable> Projection<K, V> for Pairs {
+    type Item = (K, V);
+
+    fn project(key: &[u8], value: &[u8]) -> Result<(K, V), RustyRocksError> {
+        deserialize_pair(key, value)
+    }
+}
+
+impl<K: Deserializable, V> Projection<K, V> for KeysOnly {
+    type Item = K;
+
+    fn project(key: &[u8], _value: &[u8]) -> Result<K, RustyRocksError> {
+        deserialize_key(key)
+    }
+}
+
+impl<K, V: Deserializable> Projection<K, V> for ValuesOnly {
+    type Item = V;
+
+    fn project(key: &[u8], value: &[u8]) -> Result<V, RustyRocksError> {
+        deserialize_value(key, value)
+    }
+}
+
+/// An iterator over the deserialized key-value pairs of a `KeyValueDB`, or
+/// just their keys or values, as chosen by the `Projection` `P`.
+///
+/// Both ends of the iterator read from the same snapshot, so iterating from
+/// the front and the back at once sees each pair exactly once.
+pub struct DBIter<'a, K, V, P = Pairs> {
+    front: DBRawIterator<'a>,
+    back: DBRawIterator<'a>,
+    // Whether the front walks backwards through the keys, and the back forwards.
+    reverse: bool,
+    // The last key yielded from each end; the ends have met once one of them
+    // reaches the key last yielded by the other.
+    front_key: Option<Vec<u8>>,
+    back_key: Option<Vec<u8>>,
+    done: bool,
+    // Declared after the iterators so that it is dropped after them.
+    _snapshot: Option<Snapshot<'a>>,
+    phantom: PhantomData<fn() -> (K, V)>,
+    projection: PhantomData<P>,
+}
+
+impl<K, V, P> fmt::Debug for DBIter<'_, K, V, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DBIter")
+            .field("key", &type_name::<K>())
+            .field("value", &type_name::<V>())
+            .field("projection", &type_name::<P>())
+            .field("reverse", &self.reverse)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl<'a, K, V, P> DBIter<'a, K, V, P> {
+    pub(crate) fn new(db: &'a DB) -> Self {
+        Self::with_mode(db, IteratorMode::Start)
+    }
+
+    /// Iterate starting from `mode`, towards the opposite end of the keys.
+    pub(crate) fn with_mode(db: &'a DB, mode: IteratorMode) -> Self {
+        Self::configured(db, mode, |_| {})
+    }
+
+    /// Like `with_mode`, with `configure` applied to the read options of both
+    /// ends.
+    pub(crate) fn configured<F>(db: &'a DB, mode: IteratorMode, configure: F) -> Self
+    where
+        F: Fn(&mut ReadOptions),
+    {
+        let snapshot = db.snapshot();
+        let mut iter = Self::from_snapshot_configured(&snapshot, mode, configure);
+        iter._snapshot = Some(snapshot);
+        iter
+    }
+
+    /// Like `configured`, over the column family `cf`.
+    ///
+    /// rocksdb ties column family iterators on a snapshot to the borrow of
+    /// the snapshot, so the ends cannot share one here; each reads from its
+    /// own implicit snapshot instead.
+    pub(crate) fn configured_cf<F>(
+        db: &'a DB,
+        cf: &ColumnFamily,
+        mode: IteratorMode,
+        configure: F,
+    ) -> Self
+    where
+        F: Fn(&mut ReadOptions),
+    {
+        let opts = || {
+            let mut opts = bounds(mode);
+            configure(&mut opts);
+            opts
+        };
+        let opposite = opposite(mode);
+        Self::from_cursors(
+            db.iterator_cf_opt(cf, opts(), mode).into(),
+            db.iterator_cf_opt(cf, opts(), opposite).into(),
+            matches!(opposite, IteratorMode::Start),
+        )
+    }
+
+    pub(crate) fn from_snapshot(snapshot: &Snapshot<'a>) -> Self {
+        Self::from_snapshot_configured(snapshot, IteratorMode::Start, |_| {})
+    }
+
+    fn from_snapshot_configured<F>(
+        snapshot: &Snapshot<'a>,
+        mode: IteratorMode,
+        configure: F,
+    ) -> Self
+    where
+        F: Fn(&mut ReadOptions),
+    {
+        let opposite = opposite(mode);
+        let opts = || {
+            let mut opts = bounds(mode);
+            configure(&mut opts);
+            opts
+        };
+        Self::from_cursors(
+            snapshot.iterator_opt(mode, opts()).into(),
+            snapshot.iterator_opt(opposite, opts()).into(),
+            matches!(opposite, IteratorMode::Start),
+        )
+    }
+
+    /// Iterate over the keys starting with `prefix`.
+    pub(crate) fn with_prefix(db: &'a DB, prefix: &[u8]) -> Self {
+        let snapshot = db.snapshot();
+        // Only the front seeks to a key with the prefix, so only it can make use
+        // of prefix bloom filters; the back seeks from past the prefix.
+        let mut back_opts = prefix_bounds(prefix);
+        back_opts.set_total_order_seek(true);
+        let mut iter = Self::from_cursors(
+            snapshot
+                .iterator_opt(
+                    IteratorMode::From(prefix, Direction::Forward),
+                    prefix_bounds(prefix),
+                )
+                .into(),
+            snapshot.iterator_opt(IteratorMode::End, back_opts).into(),
+            false,
+        );
+        iter._snapshot = Some(snapshot);
+        iter
+    }
+
+    /// Iterate over the keys starting with `prefix`, in descending order.
+    pub(crate) fn with_prefix_rev(db: &'a DB, prefix: &[u8]) -> Self {
+        let mut iter = Self::with_prefix(db, prefix);
+        // The ends only differ in which way they walk, so swapping them
+        // reverses the iterator.
+        std::mem::swap(&mut iter.front, &mut iter.back);
+        iter.reverse = true;
+        iter
+    }
+
+    fn from_cursors(front: DBRawIterator<'a>, back: DBRawIterator<'a>, reverse: bool) -> Self {
+        DBIter {
+            front,
+            back,
+            reverse,
+            front_key: None,
+            back_key: None,
+            done: false,
+            _snapshot: None,
+            phantom: PhantomData,
+            projection: PhantomData,
+        }
+    }
+
+    /// Yield just the keys, without deserializing the values.
+    pub fn keys(self) -> DBIter<'a, K, V, KeysOnly> {
+        self.project()
+    }
+
+    /// Yield just the values, without deserializing the keys.
+    pub fn values(self) -> DBIter<'a, K, V, ValuesOnly> {
+        self.project()
+    }
+
+    /// Yield what `Q` projects from each entry instead.
+    pub fn project<Q>(self) -> DBIter<'a, K, V, Q> {
+        DBIter {
+            front: self.front,
+            back: self.back,
+            reverse: self.reverse,
+            front_key: self.front_key,
+            back_key: self.back_key,
+            done: self.done,
+            _snapshot: self._snapshot,
+            phantom: PhantomData,
+            projection: PhantomData,
+        }
+    }
+
+    /// Pass every error to `on_error` and carry on with the next pair,
+    /// rather than yielding it.
+    ///
+    /// Pairs that fail to deserialize are skipped. An error reading from
+    /// rocksdb itself still ends the iteration, after being passed on.
+    pub fn skip_errors<F>(self, on_error: F) -> SkipErrors<'a, K, V, F, P>
+    where
+        F: FnMut(RustyRocksError),
+    {
+        SkipErrors {
+            inner: self,
+            on_error,
+        }
+    }
+}
+
+// Where the back of an iterator starting from `mode` starts.
+fn opposite(mode: IteratorMode) -> IteratorMode<'static> {
+    match mode {
+        IteratorMode::Start | IteratorMode::From(_, Direction::Forward) => IteratorMode::End,
+        IteratorMode::End | IteratorMode::From(_, Direction::Reverse) => IteratorMode::Start,
+    }
+}
+
+// Bound both ends by the starting key, so that the back cannot run past it.
+fn bounds(mode: IteratorMode) -> ReadOptions {
+    let mut opts = ReadOptions::default();
+    // Seeking to an arbitrary key must not be cut short by a prefix extractor.
+    opts.set_total_order_seek(true);
+    match mode {
+        IteratorMode::From(start, Direction::Forward) => opts.set_iterate_lower_bound(start),
+        IteratorMode::From(start, Direction::Reverse) => {
+            // The upper bound is exclusive, and this is the next key after `start`.
+            let mut upper = start.to_vec();
+            upper.push(0);
+            opts.set_iterate_upper_bound(upper);
+        }
+        IteratorMode::Start | IteratorMode::End => {}
+    }
+    opts
+}
+
+pub(crate) fn prefix_bounds(prefix: &[u8]) -> ReadOptions {
+    let mut opts = ReadOptions::default();
+    opts.set_iterate_lower_bound(prefix);
+    if let Some(upper) = prefix_successor(prefix) {
+        opts.set_iterate_upper_bound(upper);
+    }
+    opts
+}
+
+/// The first key past every key starting with `prefix`.
+///
+/// This comes from incrementing the last byte of the prefix that is not
+/// already 0xff. A prefix of all 0xff bytes has no such key.
+pub(crate) fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let i = prefix.iter().rposition(|&b| b != 0xff)?;
+    let mut upper = prefix[..=i].to_vec();
+    upper[i] += 1;
+    Some(upper)
+}
+
+fn remember(slot: &mut Option<Vec<u8>>, key: &[u8]) {
+    let buf = slot.get_or_insert_with(Vec::new);
+    buf.clear();
+    buf.extend_from_slice(key);
+}
+
+impl<'a, K, V, P: Projection<K, V>> Iterator for DBIter<'a, K, V, P> {
+    type Item = Result<P::Item, RustyRocksError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let (k, v) = match self.front.item() {
+            Some(kv) => kv,
+            None => {
+                self.done = true;
+                return self.front.status().err().map(|e| Err(e.into()));
+            }
+        };
+        if self.back_key.as_deref() == Some(k) {
+            self.done = true;
+            return None;
+        }
+        let item = P::project(k, v);
+        remember(&mut self.front_key, k);
+        if self.reverse {
+            self.front.prev();
+        } else {
+            self.front.next();
+        }
+        Some(item)
+    }
+}
+
+impl<'a, K, V, P: Projection<K, V>> DoubleEndedIterator for DBIter<'a, K, V, P> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let (k, v) = match self.back.item() {
+            Some(kv) => kv,
+            None => {
+                self.done = true;
+                return self.back.status().err().map(|e| Err(e.into()));
+            }
+        };
+        if self.front_key.as_deref() == Some(k) {
+            self.done = true;
+            return None;
+        }
+        let item = P::project(k, v);
+        remember(&mut self.back_key, k);
+        if self.reverse {
+            self.back.next();
+        } else {
+            self.back.prev();
+        }
+        Some(item)
+    }
+}
+
+/// A `DBIter` that hands errors to a callback instead of yielding them.
+///
+/// Created by `DBIter::skip_errors`.
+pub struct SkipErrors<'a, K, V, F, P = Pairs> {
+    inner: DBIter<'a, K, V, P>,
+    on_error: F,
+}
+
+impl<'a, K, V, F, P> Iterator for SkipErrors<'a, K, V, F, P>
+where
+    P: Projection<K, V>,
+    F: FnMut(RustyRocksError),
+{
+    type Item = P::Item;
+
+    fn next(&mut self) -> Option<P::Item> {
+        loop {
+            match self.inner.next()? {
+                Ok(kv) => return Some(kv),
+                Err(e) => (self.on_error)(e),
+            }
+        }
+    }
+}
+
+impl<'a, K, V, F, P> DoubleEndedIterator for SkipErrors<'a, K, V, F, P>
+where
+    P: Projection<K, V>,
+    F: FnMut(RustyRocksError),
+{
+    fn next_back(&mut self) -> Option<P::Item> {
+        loop {
+            match self.inner.next_back()? {
+                Ok(kv) => return Some(kv),
+                Err(e) => (self.on_error)(e),
+            }
+        }
+    }
+}
+
+// How many pairs an `OwnedDBIter` reads each time it seeks.
+const OWNED_PAGE_SIZE: usize = 256;
+
+type RawPair = (Box<[u8]>, Box<[u8]>);
+
+/// An iterator over the deserialized key-value pairs of a `KeyValueDB` that
+/// shares ownership of the database rather than borrowing it.
+///
+/// Created by `KeyValueDB::owned_iter`. It is `'static`, so it can be
+/// returned or sent to another thread. A rocksdb iterator cannot outlive a
+/// borrow of the database, so this reads pairs a page at a time, each page
+/// from a fresh iterator that seeks past the last key read. Unlike `DBIter`,
+/// it therefore does not read from a single snapshot: writes made while it
+/// runs may or may not be seen.
+pub struct OwnedDBIter<K, V> {
+    db: Arc<DB>,
+    page: VecDeque<RawPair>,
+    last_key: Option<Box<[u8]>>,
+    done: bool,
+    phantom: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> OwnedDBIter<K, V> {
+    pub(crate) fn new(db: Arc<DB>) -> Self {
+        OwnedDBIter {
+            db,
+            page: VecDeque::new(),
+            last_key: None,
+            done: false,
+            phantom: PhantomData,
+        }
+    }
+
+    fn read_page(&mut self) -> Result<(), rocksdb::Error> {
+        let mut opts = ReadOptions::default();
+        opts.set_total_order_seek(true);
+        let mut iter = self.db.raw_iterator_opt(opts);
+        match &self.last_key {
+            Some(last) => {
+                iter.seek(last);
+                if iter.key() == Some(last) {
+                    iter.next();
+                }
+            }
+            None => iter.seek_to_first(),
+        }
+        while self.page.len() < OWNED_PAGE_SIZE {
+            match iter.item() {
+                Some((k, v)) => self.page.push_back((k.into(), v.into())),
+                None => {
+                    iter.status()?;
+                    self.done = true;
+                    break;
+                }
+            }
+            iter.next();
+        }
+        if let Some((k, _)) = self.page.back() {
+            self.last_key = Some(k.clone());
+        }
+        Ok(())
+    }
+}
+
+impl<K: Deserializable, V: Deserializable> Iterator for OwnedDBIter<K, V> {
+    type Item = Result<(K, V), RustyRocksError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.page.is_empty() && !self.done {
+            if let Err(e) = self.read_page() {
+                self.done = true;
+                return Some(Err(e.into()));
+            }
+        }
+        let (k, v) = self.page.pop_front()?;
+        Some(deserialize_pair(&k, &v))
+    }
+}
@@ -0,0 +1,124 @@
+//! JSON documents updated by merge patches.
+
+use serde_json::{Deserializer, Map, Value};
+
+use crate::{AssociateMergeable, Deserializable, MergePolicy, TrySerializable};
+
+/// A JSON document, merged by applying JSON merge patches (RFC 7386) to it in
+/// the order they were written.
+///
+/// The first value written to a key is the document, taken as it is. Each
+/// value merged in after it is a patch: its objects are merged into the
+/// document's key by key, a `null` deletes the key it is under, and anything
+/// else replaces what was there.
+///
+/// Two patches cannot always be combined into one, so when rocksdb merges
+/// operands ahead of time they are kept as a list, stored as one JSON value
+/// after another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonMerge(Vec<Value>);
+
+impl JsonMerge {
+    /// A document or a patch, to put or merge into a key.
+    pub fn new(value: Value) -> Self {
+        JsonMerge(vec![value])
+    }
+
+    /// The document, with every patch applied.
+    pub fn into_value(self) -> Value {
+        let mut values = self.0.into_iter();
+        let mut doc = values.next().unwrap_or(Value::Null);
+        for patch in values {
+            apply_patch(&mut doc, patch);
+        }
+        doc
+    }
+}
+
+impl From<Value> for JsonMerge {
+    fn from(value: Value) -> Self {
+        JsonMerge::new(value)
+    }
+}
+
+// RFC 7386, section 2.
+fn apply_patch(target: &mut Value, patch: Value) {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        patch => {
+            *target = patch;
+            return;
+        }
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(&key);
+            } else {
+                apply_patch(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+impl TrySerializable for &JsonMerge {
+    type Bytes = Vec<u8>;
+    type Error = serde_json::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, serde_json::Error> {
+        let mut bytes = Vec::new();
+        for (i, value) in self.0.iter().enumerate() {
+            if i > 0 {
+                bytes.push(b'\n');
+            }
+            serde_json::to_writer(&mut bytes, value)?;
+        }
+        Ok(bytes)
+    }
+}
+
+impl Deserializable for JsonMerge {
+    type Error = serde_json::Error;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        let values = Deserializer::from_slice(bytes)
+            .into_iter()
+            .collect::<Result<Vec<Value>, _>>()?;
+        if values.is_empty() {
+            // No value at all is an error, which parsing a single one reports.
+            serde_json::from_slice::<Value>(bytes)?;
+        }
+        Ok(JsonMerge(values))
+    }
+}
+
+impl AssociateMergeable for JsonMerge {
+    fn merge(&mut self, other: Self) {
+        let mut doc = std::mem::replace(self, JsonMerge(Vec::new())).into_value();
+        for patch in other.0 {
+            apply_patch(&mut doc, patch);
+        }
+        *self = JsonMerge::new(doc);
+    }
+
+    fn partial_merge(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        (&self)
+            .try_serialize()
+            .expect("serde_json can encode any JSON value")
+    }
+
+    fn handle_deser_error(
+        _key: &[u8],
+        _bytes: &[u8],
+        _err: serde_json::Error,
+    ) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
@@ -0,0 +1,136 @@
+//! Databases that record when each key was last written.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rocksdb::{ColumnFamily, Options, WriteBatch};
+
+use crate::{
+    deserialize, serialize, DBIter, Deserializable, KeyRef, KeyValueDB, RustyRocksError,
+    Serializable, TrySerializable,
+};
+
+// The column family holding each key's last-modified time.
+const META_CF: &str = "__meta";
+
+/// A `KeyValueDB` that records when each key was last written.
+///
+/// Every write also stores a timestamp for its key in the `__meta` column
+/// family, in the same write batch, so the two are always in step. The
+/// timestamps are microseconds since the Unix epoch, and strictly increase
+/// from one write to the next while the database is open, even if the clock
+/// goes backwards.
+pub struct LastModifiedDB<KRef, V, VRef, K = <KRef as KeyRef>::Owned> {
+    kv: KeyValueDB<KRef, V, VRef, K>,
+    // The last timestamp handed out.
+    clock: AtomicU64,
+}
+
+impl<KRef, V, VRef, K> LastModifiedDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        Self::open_with_opts(path, opts)
+    }
+
+    /// Open the database at `path` with the given rocksdb options, e.g. to
+    /// install a merge operator for `merge`.
+    ///
+    /// The `__meta` column family is created if it is missing, whatever
+    /// `opts` says.
+    pub fn open_with_opts<P: AsRef<Path>>(
+        path: P,
+        mut opts: Options,
+    ) -> Result<Self, RustyRocksError> {
+        opts.create_missing_column_families(true);
+        Ok(LastModifiedDB {
+            kv: KeyValueDB::open_cf_with_opts(path, opts, [META_CF])?,
+            clock: AtomicU64::new(0),
+        })
+    }
+
+    fn meta(&self) -> Result<&ColumnFamily, RustyRocksError> {
+        self.kv
+            .db
+            .cf_handle(META_CF)
+            .ok_or_else(|| RustyRocksError::UnknownColumnFamily(META_CF.to_owned()))
+    }
+
+    fn now(&self) -> u64 {
+        let wall = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+        let next = |last: u64| wall.max(last + 1);
+        match self
+            .clock
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(next(last))
+            }) {
+            Ok(last) | Err(last) => next(last),
+        }
+    }
+
+    // Write `batch` along with a new timestamp for `key`.
+    fn write_stamped(&self, mut batch: WriteBatch, key: &[u8]) -> Result<(), RustyRocksError> {
+        batch.put_cf(self.meta()?, key, self.now().serialize());
+        self.kv.db.write(batch)?;
+        Ok(())
+    }
+
+    pub fn get(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        self.kv.get(k)
+    }
+
+    /// When `k` was last written, in microseconds since the Unix epoch, or
+    /// `None` if it has not been written or has been deleted.
+    pub fn last_modified(&self, k: KRef) -> Result<Option<u64>, RustyRocksError> {
+        match self.kv.db.get_pinned_cf(self.meta()?, serialize(k)?)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.kv.check_writable()?;
+        let key = serialize(k)?;
+        let mut batch = WriteBatch::default();
+        batch.put(&key, serialize(v)?);
+        self.write_stamped(batch, key.as_ref())
+    }
+
+    /// Merge `v` into the value for `k` with the merge operator installed in
+    /// the options passed to `open_with_opts`, as `KeyValueDB::merge_raw`
+    /// does.
+    pub fn merge(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.kv.check_writable()?;
+        let key = serialize(k)?;
+        let mut batch = WriteBatch::default();
+        batch.merge(&key, serialize(v)?);
+        self.write_stamped(batch, key.as_ref())
+    }
+
+    /// Delete `k` along with its timestamp.
+    pub fn delete(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.kv.check_writable()?;
+        let key = serialize(k)?;
+        let mut batch = WriteBatch::default();
+        batch.delete(&key);
+        batch.delete_cf(self.meta()?, &key);
+        self.kv.db.write(batch)?;
+        Ok(())
+    }
+
+    pub fn db_iter(&self) -> DBIter<'_, K, V>
+    where
+        K: Deserializable,
+    {
+        self.kv.db_iter()
+    }
+}
@@ -0,0 +1,1743 @@
+//! A safe, rustic interface for rocksdb that keeps data typed.
+
+use std::any::type_name;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::str::Utf8Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rocksdb::backup::{BackupEngine, BackupEngineOptions, RestoreOptions};
+use rocksdb::checkpoint::Checkpoint;
+use rocksdb::compaction_filter::Decision as CompactionDecision;
+use rocksdb::{
+    BlockBasedOptions, Cache, DBRawIterator, Direction, Env, ErrorKind, IteratorMode, Options,
+    ReadOptions, SliceTransform, WriteBatch, WriteOptions, DB,
+};
+
+mod append_log;
+#[cfg(feature = "tokio")]
+mod async_db;
+mod bounded;
+mod bulk;
+mod codec;
+mod column_family;
+mod composite;
+mod counter;
+mod error;
+mod field_map;
+mod histogram;
+mod indexed_batch;
+mod iter;
+#[cfg(feature = "json")]
+mod json_merge;
+mod last_modified;
+mod merge;
+mod metadata;
+mod min_max;
+mod operation;
+mod pinned;
+mod primitives;
+pub mod properties;
+mod replace_merge;
+mod simple;
+mod snapshot;
+mod sst;
+mod string_list;
+mod temporary;
+#[cfg(feature = "test-util")]
+pub mod testutil;
+mod transaction;
+mod ttl;
+mod versioned;
+mod wal;
+
+pub use append_log::AppendLog;
+#[cfg(feature = "tokio")]
+pub use async_db::AsyncKeyValueDB;
+pub use bounded::BoundedSet;
+pub use bulk::BulkLoader;
+#[cfg(feature = "json")]
+pub use codec::JsonCodec;
+pub use codec::{Bincode, BincodeCodec, Codec, Encoded, MsgPackCodec, MsgPackError};
+pub use column_family::{BatchOp, ColumnFamilyBuilder};
+pub use composite::CompositeKey;
+pub use counter::CounterDB;
+pub use error::{
+    CompositeKeyError, FieldMapError, LengthError, PrimitiveError, RustyRocksError,
+    StringListError, TupleError, VersionedError,
+};
+pub use field_map::{FieldMap, FieldMapDB};
+pub use histogram::HistogramDB;
+pub use indexed_batch::TypedIndexedBatch;
+pub use iter::{DBIter, KeysOnly, OwnedDBIter, Pairs, Projection, SkipErrors, ValuesOnly};
+#[cfg(feature = "json")]
+pub use json_merge::JsonMerge;
+pub use last_modified::LastModifiedDB;
+pub use merge::{AssociateMergeable, MergeError, MergePolicy, MergeableDB, MergeableDBBuilder};
+pub use metadata::LiveFileMeta;
+pub use min_max::MinMaxDB;
+pub use operation::{Operate, Operation, Operations};
+pub use pinned::PinnedValue;
+pub use primitives::FixedWidth;
+pub use replace_merge::ReplaceMerge;
+pub use simple::SimpleDB;
+pub use snapshot::Snapshot;
+pub use sst::SstWriter;
+pub use string_list::SortedStringList;
+pub use temporary::TemporaryDB;
+pub use transaction::{Transaction, TransactionalDB};
+pub use ttl::TtlDB;
+pub use versioned::{Versioned, VersionedValue};
+pub use wal::{Update, Updates};
+
+/// A type that can be written to the database.
+pub trait Serializable {
+    type Bytes: AsRef<[u8]>;
+
+    fn serialize(self) -> Self::Bytes;
+
+    /// Append the serialized bytes to `buf`, so that one buffer can be reused
+    /// across many values.
+    ///
+    /// The default copies the result of `serialize`; types whose `Bytes` is
+    /// a freshly allocated `Vec` can override it to write to `buf` directly.
+    fn serialize_into(self, buf: &mut Vec<u8>)
+    where
+        Self: Sized,
+    {
+        buf.extend_from_slice(self.serialize().as_ref());
+    }
+}
+
+/// A type whose serialization can fail.
+///
+/// Every `Serializable` type is also `TrySerializable`, with an `Infallible` error.
+pub trait TrySerializable {
+    type Bytes: AsRef<[u8]>;
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    fn try_serialize(self) -> Result<Self::Bytes, Self::Error>;
+
+    /// Append the serialized bytes to `buf`, as `Serializable::serialize_into`
+    /// does.
+    fn try_serialize_into(self, buf: &mut Vec<u8>) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        buf.extend_from_slice(self.try_serialize()?.as_ref());
+        Ok(())
+    }
+}
+
+impl<T: Serializable> TrySerializable for T {
+    type Bytes = T::Bytes;
+    type Error = Infallible;
+
+    fn try_serialize(self) -> Result<Self::Bytes, Self::Error> {
+        Ok(self.serialize())
+    }
+
+    fn try_serialize_into(self, buf: &mut Vec<u8>) -> Result<(), Self::Error> {
+        self.serialize_into(buf);
+        Ok(())
+    }
+}
+
+fn serialize<T: TrySerializable>(t: T) -> Result<T::Bytes, RustyRocksError> {
+    t.try_serialize().map_err(RustyRocksError::serialize)
+}
+
+// Serialize `t` into `buf`, replacing what it held.
+fn serialize_into<T: TrySerializable>(t: T, buf: &mut Vec<u8>) -> Result<(), RustyRocksError> {
+    buf.clear();
+    t.try_serialize_into(buf)
+        .map_err(RustyRocksError::serialize)
+}
+
+/// A type that can be read from the database, producing an owned value.
+pub trait Deserializable: Sized {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Self::Error>;
+}
+
+fn deserialize<T: Deserializable>(bytes: &[u8]) -> Result<T, RustyRocksError> {
+    T::deserialize(bytes).map_err(RustyRocksError::deserialize)
+}
+
+// Iterators read keys and values they were not asked for by name, so their
+// errors say which one failed and carry its bytes.
+fn deserialize_key<K: Deserializable>(bytes: &[u8]) -> Result<K, RustyRocksError> {
+    K::deserialize(bytes).map_err(|e| RustyRocksError::KeyDeserialize {
+        bytes: bytes.to_vec(),
+        source: Box::new(e),
+    })
+}
+
+fn deserialize_value<V: Deserializable>(key: &[u8], bytes: &[u8]) -> Result<V, RustyRocksError> {
+    V::deserialize(bytes).map_err(|e| RustyRocksError::ValueDeserialize {
+        key: key.to_vec(),
+        bytes: bytes.to_vec(),
+        source: Box::new(e),
+    })
+}
+
+fn deserialize_pair<K: Deserializable, V: Deserializable>(
+    key: &[u8],
+    value: &[u8],
+) -> Result<(K, V), RustyRocksError> {
+    Ok((deserialize_key(key)?, deserialize_value(key, value)?))
+}
+
+/// A type that can be read from the database by borrowing from the stored
+/// bytes, without copying them.
+pub trait BorrowDeserialize<'a>: Sized {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    fn deserialize_borrowed(bytes: &'a [u8]) -> Result<Self, Self::Error>;
+}
+
+/// Associates a key type, as written, with the owned type it is read back as.
+///
+/// This lets a database infer the key type its iterators yield from `KRef`:
+/// keys written as `&str` are read back as `String`, while keys like `u32`
+/// are read back as themselves.
+pub trait KeyRef {
+    type Owned: Deserializable;
+}
+
+impl KeyRef for &str {
+    type Owned = String;
+}
+
+impl<'a> Serializable for &'a str {
+    type Bytes = &'a [u8];
+
+    fn serialize(self) -> Self::Bytes {
+        self.as_bytes()
+    }
+}
+
+impl KeyRef for String {
+    type Owned = String;
+}
+
+impl Serializable for String {
+    type Bytes = String;
+
+    fn serialize(self) -> Self::Bytes {
+        self
+    }
+}
+
+impl Deserializable for String {
+    type Error = Utf8Error;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Self::Error> {
+        std::str::from_utf8(bytes).map(str::to_owned)
+    }
+}
+
+impl<'a> BorrowDeserialize<'a> for &'a str {
+    type Error = Utf8Error;
+
+    fn deserialize_borrowed(bytes: &'a [u8]) -> Result<Self, Self::Error> {
+        std::str::from_utf8(bytes)
+    }
+}
+
+// Raw bytes are stored as they are, for using a database as a plain byte store.
+
+impl KeyRef for &[u8] {
+    type Owned = Vec<u8>;
+}
+
+impl KeyRef for Vec<u8> {
+    type Owned = Vec<u8>;
+}
+
+impl<'a> Serializable for &'a [u8] {
+    type Bytes = &'a [u8];
+
+    fn serialize(self) -> Self::Bytes {
+        self
+    }
+}
+
+impl Serializable for Vec<u8> {
+    type Bytes = Vec<u8>;
+
+    fn serialize(self) -> Self::Bytes {
+        self
+    }
+}
+
+impl Deserializable for Vec<u8> {
+    type Error = Infallible;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Infallible> {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Whether a compaction filter keeps an entry, for
+/// `KeyValueDB::open_with_compaction_filter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterDecision {
+    Keep,
+    Remove,
+}
+
+// A page of pairs from `KeyValueDB::page`, and the cursor for the next one.
+type Page<K, V> = (Vec<(K, V)>, Option<K>);
+
+/// A typed wrapper around a rocksdb database.
+///
+/// Keys are written as `KRef` and read back as `K`, which is inferred from
+/// `KRef` through `KeyRef` unless given explicitly. Values are written as
+/// `VRef` and read back as `V`.
+///
+/// Cloning a `KeyValueDB` is cheap: clones share the open database, so each
+/// thread can be handed its own clone rather than an `Arc<KeyValueDB>`.
+///
+/// ```no_run
+/// use rustyrocks::KeyValueDB;
+///
+/// let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default("names.db").unwrap();
+/// db.put("ada", "lovelace").unwrap();
+/// for kv in db.db_iter() {
+///     let (first, last) = kv.unwrap();
+///     // The key is read back as a `String`, inferred from `&str`.
+///     let first: String = first;
+///     println!("{} {}", first, last);
+/// }
+/// ```
+pub struct KeyValueDB<KRef, V, VRef, K = <KRef as KeyRef>::Owned> {
+    db: Arc<DB>,
+    read_only: bool,
+    // The options the database was opened with, kept to read statistics from.
+    stats_opts: Option<Options>,
+    // Every column family in the database, or none if only the default one
+    // was opened.
+    column_families: Vec<String>,
+    phantom: PhantomData<(KRef, V, VRef, K)>,
+}
+
+impl<KRef, V, VRef, K> Clone for KeyValueDB<KRef, V, VRef, K> {
+    fn clone(&self) -> Self {
+        KeyValueDB {
+            db: Arc::clone(&self.db),
+            read_only: self.read_only,
+            stats_opts: self.stats_opts.clone(),
+            column_families: self.column_families.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Writes the pairs as `try_extend` does.
+///
+/// # Panics
+///
+/// If a pair fails to serialize or the write fails, since `extend` cannot
+/// return an error.
+impl<KRef, V, VRef, K> Extend<(KRef, VRef)> for &KeyValueDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    fn extend<I: IntoIterator<Item = (KRef, VRef)>>(&mut self, pairs: I) {
+        if let Err(e) = self.try_extend(pairs) {
+            panic!("failed to extend database: {}", e);
+        }
+    }
+}
+
+// Only the path and the types, since values could be large or sensitive.
+impl<KRef, V, VRef, K> fmt::Debug for KeyValueDB<KRef, V, VRef, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyValueDB")
+            .field("path", &self.db.path())
+            .field("read_only", &self.read_only)
+            .field("key", &type_name::<K>())
+            .field("value", &type_name::<V>())
+            .finish()
+    }
+}
+
+impl<KRef, V, VRef, K> KeyValueDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn open_default<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        Ok(Self::from_db(DB::open_default(path)?, false))
+    }
+
+    /// Open the database at `path` with the given rocksdb options.
+    pub fn open_with_opts<P: AsRef<Path>>(path: P, opts: Options) -> Result<Self, RustyRocksError> {
+        Ok(Self::from_db(DB::open(&opts, path)?, false))
+    }
+
+    /// Open the database at `path`, creating it if it does not exist, with its
+    /// block cache drawn from `cache`.
+    ///
+    /// Databases opened with clones of the same `Cache` share its capacity,
+    /// rather than each holding a cache of its own.
+    pub fn open_with_cache<P: AsRef<Path>>(
+        path: P,
+        cache: &Cache,
+    ) -> Result<Self, RustyRocksError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let mut table_opts = BlockBasedOptions::default();
+        table_opts.set_block_cache(cache);
+        opts.set_block_based_table_factory(&table_opts);
+        Self::open_with_opts(path, opts)
+    }
+
+    /// Open the database at `path`, creating it if it does not exist, with
+    /// its file access and background threads provided by `env`.
+    ///
+    /// `Env::mem_env` keeps the database in memory only, which suits tests;
+    /// an env with fewer or lower-priority background threads throttles
+    /// flushes and compactions. The database keeps its own reference to
+    /// `env`, so it stays alive for as long as the database is open.
+    pub fn open_with_env<P: AsRef<Path>>(path: P, env: &Env) -> Result<Self, RustyRocksError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_env(env);
+        Self::open_with_opts(path, opts)
+    }
+
+    /// Open the database at `path`, creating it if it does not exist, with
+    /// rocksdb's internal statistics collected for `statistics`.
+    ///
+    /// Collecting statistics costs a few percent of throughput.
+    pub fn open_with_stats<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.enable_statistics();
+        let mut kv = Self::from_db(DB::open(&opts, path)?, false);
+        // Copies of the options share their statistics, which the copy the
+        // database was opened with does not expose.
+        kv.stats_opts = Some(opts);
+        Ok(kv)
+    }
+
+    /// Open the database at `path`, creating it if it does not exist, with the
+    /// first `prefix_len` bytes of each serialized key used as its prefix.
+    ///
+    /// This adds prefix bloom filters, so that `db_iter_prefix` can skip files
+    /// and memtables holding no keys with the prefix. `db_iter_prefix` only
+    /// yields keys with its prefix either way; the extractor just makes it
+    /// cheaper. Iterating with prefixes of exactly `prefix_len` bytes gets the
+    /// most out of the filters.
+    pub fn open_with_prefix<P: AsRef<Path>>(
+        path: P,
+        prefix_len: usize,
+    ) -> Result<Self, RustyRocksError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(prefix_len));
+        opts.set_memtable_prefix_bloom_ratio(0.1);
+        let mut table_opts = BlockBasedOptions::default();
+        table_opts.set_bloom_filter(10.0, false);
+        opts.set_block_based_table_factory(&table_opts);
+        Self::open_with_opts(path, opts)
+    }
+
+    /// Open the database at `path`, creating it if it does not exist, with keys
+    /// ordered by `cmp` on their serialized bytes instead of bytewise.
+    ///
+    /// `cmp` must be deterministic and a total order. rocksdb records `name`
+    /// and refuses to open the database under a different one, but it cannot
+    /// tell if the ordering behind a name changes: doing that to an existing
+    /// database corrupts its ordering. `db_iter_prefix` and the reverse mode of
+    /// `db_iter_from` compute bounds bytewise, so they assume an ordering
+    /// compatible with that.
+    pub fn open_with_comparator<P: AsRef<Path>>(
+        path: P,
+        name: &str,
+        cmp: fn(&[u8], &[u8]) -> Ordering,
+    ) -> Result<Self, RustyRocksError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_comparator(name, cmp);
+        Self::open_with_opts(path, opts)
+    }
+
+    /// Open the database at `path`, creating it if it does not exist, with
+    /// `filter` run on every entry that compaction rewrites.
+    ///
+    /// The filter sees the serialized key and value, and deserializing them is
+    /// up to it. Entries it removes disappear once compaction reaches them,
+    /// which may be long after they are written; call `compact_all` to filter
+    /// everything at once.
+    pub fn open_with_compaction_filter<P, F>(
+        path: P,
+        name: &str,
+        mut filter: F,
+    ) -> Result<Self, RustyRocksError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&[u8], &[u8]) -> FilterDecision + Send + 'static,
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_compaction_filter(
+            name,
+            move |_level: u32, key: &[u8], value: &[u8]| match filter(key, value) {
+                FilterDecision::Keep => CompactionDecision::Keep,
+                FilterDecision::Remove => CompactionDecision::Remove,
+            },
+        );
+        Self::open_with_opts(path, opts)
+    }
+
+    /// Open an existing database at `path` without write access.
+    ///
+    /// Several processes can open the same database read-only. Writes return
+    /// `RustyRocksError::ReadOnly`.
+    pub fn open_read_only<P: AsRef<Path>>(
+        path: P,
+        error_if_log_file_exist: bool,
+    ) -> Result<Self, RustyRocksError> {
+        Self::open_read_only_with_opts(path, Options::default(), error_if_log_file_exist)
+    }
+
+    pub fn open_read_only_with_opts<P: AsRef<Path>>(
+        path: P,
+        opts: Options,
+        error_if_log_file_exist: bool,
+    ) -> Result<Self, RustyRocksError> {
+        let db = DB::open_for_read_only(&opts, path, error_if_log_file_exist)?;
+        Ok(Self::from_db(db, true))
+    }
+
+    /// Open the database at `primary_path` as a read-only secondary, keeping
+    /// its own logs in `secondary_path`.
+    ///
+    /// Unlike a read-only handle, a secondary can follow the primary's writes
+    /// by calling `try_catch_up_with_primary`.
+    pub fn open_secondary<P: AsRef<Path>, S: AsRef<Path>>(
+        primary_path: P,
+        secondary_path: S,
+    ) -> Result<Self, RustyRocksError> {
+        let mut opts = Options::default();
+        // Secondaries must keep every file open to notice the primary's.
+        opts.set_max_open_files(-1);
+        let db = DB::open_as_secondary(&opts, primary_path.as_ref(), secondary_path.as_ref())?;
+        Ok(Self::from_db(db, true))
+    }
+
+    /// Replay whatever the primary has written since this secondary last
+    /// caught up.
+    pub fn try_catch_up_with_primary(&self) -> Result<(), RustyRocksError> {
+        self.db.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
+    /// Catch up with the primary every `interval`, calling `on_update` each
+    /// time doing so brings in new writes, until `on_update` returns false.
+    ///
+    /// This blocks the calling thread, so it is usually run on a thread of
+    /// its own, sharing the database through an `Arc`.
+    pub fn follow<F>(&self, interval: Duration, mut on_update: F) -> Result<(), RustyRocksError>
+    where
+        F: FnMut() -> bool,
+    {
+        let mut seen = self.db.latest_sequence_number();
+        loop {
+            std::thread::sleep(interval);
+            self.try_catch_up_with_primary()?;
+            let latest = self.db.latest_sequence_number();
+            if latest != seen {
+                seen = latest;
+                if !on_update() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// The sequence number of the most recent write.
+    pub fn latest_sequence_number(&self) -> u64 {
+        self.db.latest_sequence_number()
+    }
+
+    /// Read back the write batches made after the one holding the write
+    /// with sequence number `seq`, such as one returned by
+    /// `latest_sequence_number`, for change data capture.
+    ///
+    /// The batch holding `seq` itself is left out, as rocksdb 0.19's log
+    /// iterator always steps past the batch it starts on; this also means
+    /// the first batch ever written cannot be read back.
+    ///
+    /// Only puts and deletes in the default column family can be read back.
+    /// Merges are left out of their batch. A range delete or a write to
+    /// another column family stops its batch being read, so the writes after
+    /// it in the same batch are lost as well, even ones to the default column
+    /// family. A batch with writes left out ends with `Update::Undecoded`,
+    /// giving how many.
+    ///
+    /// Updates are only available for as long as the logs holding them are
+    /// kept, which `Options::set_wal_ttl_seconds` and `set_wal_size_limit_mb`
+    /// control; asking for older ones fails.
+    pub fn updates_since(&self, seq: u64) -> Result<Updates<K, V>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        Ok(Updates::new(self.db.get_updates_since(seq)?))
+    }
+
+    /// Delete the database at `path` and everything in its directory.
+    ///
+    /// Every handle to the database, including async handles, must have been
+    /// dropped first; rocksdb refuses to destroy a database that is still open.
+    pub fn destroy<P: AsRef<Path>>(path: P, opts: &Options) -> Result<(), RustyRocksError> {
+        DB::destroy(opts, path)?;
+        Ok(())
+    }
+
+    /// Try to recover the database at `path` when corruption stops it
+    /// opening, rebuilding its manifest from whatever SST files and
+    /// write-ahead logs can still be read.
+    ///
+    /// Some data may be lost, such as anything in files that cannot be read.
+    /// Run this only while nothing has the database open, on a copy if the
+    /// data matters. Pass the options the database is normally opened with,
+    /// e.g. its comparator.
+    pub fn repair<P: AsRef<Path>>(path: P, opts: &Options) -> Result<(), RustyRocksError> {
+        DB::repair(opts, path)?;
+        Ok(())
+    }
+
+    /// Restore the most recent backup in `backup_dir` into `db_dir`, which
+    /// must not be open.
+    pub fn restore_from_backup<B: AsRef<Path>, D: AsRef<Path>>(
+        backup_dir: B,
+        db_dir: D,
+    ) -> Result<(), RustyRocksError> {
+        let mut engine = BackupEngine::open(&BackupEngineOptions::default(), backup_dir)?;
+        let db_dir = db_dir.as_ref();
+        engine.restore_from_latest_backup(db_dir, db_dir, &RestoreOptions::default())?;
+        Ok(())
+    }
+
+    /// Delete all but the `keep` most recent backups in `backup_dir`.
+    pub fn purge_old_backups<B: AsRef<Path>>(
+        backup_dir: B,
+        keep: usize,
+    ) -> Result<(), RustyRocksError> {
+        let mut engine = BackupEngine::open(&BackupEngineOptions::default(), backup_dir)?;
+        engine.purge_old_backups(keep)?;
+        Ok(())
+    }
+
+    /// The path the database was opened at, e.g. for passing to `destroy`
+    /// once the database has been dropped.
+    pub fn path(&self) -> &Path {
+        self.db.path()
+    }
+
+    fn from_db(db: DB, read_only: bool) -> Self {
+        KeyValueDB {
+            db: Arc::new(db),
+            read_only,
+            stats_opts: None,
+            column_families: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    fn check_writable(&self) -> Result<(), RustyRocksError> {
+        if self.read_only {
+            return Err(RustyRocksError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        match self.db.get_pinned(serialize(k)?)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Like `get`, keeping a value that fails to deserialize apart from a
+    /// failure to read it.
+    ///
+    /// The outer error is from rocksdb or from serializing `k`; the inner one
+    /// means `k` has a value but it is not a valid `V`.
+    pub fn try_get(&self, k: KRef) -> Result<Option<Result<V, V::Error>>, RustyRocksError> {
+        Ok(self
+            .db
+            .get_pinned(serialize(k)?)?
+            .map(|bytes| V::deserialize(&bytes)))
+    }
+
+    /// Like `get`, returning `default` if `k` has no value.
+    pub fn get_or(&self, k: KRef, default: V) -> Result<V, RustyRocksError> {
+        Ok(self.get(k)?.unwrap_or(default))
+    }
+
+    /// Like `get`, returning the result of `f` if `k` has no value.
+    pub fn get_or_else<F>(&self, k: KRef, f: F) -> Result<V, RustyRocksError>
+    where
+        F: FnOnce() -> V,
+    {
+        Ok(self.get(k)?.unwrap_or_else(f))
+    }
+
+    /// Like `get`, with `configure` applied to the read options first, e.g. to
+    /// call `set_verify_checksums(false)`.
+    pub fn get_opt<F>(&self, k: KRef, configure: F) -> Result<Option<V>, RustyRocksError>
+    where
+        F: FnOnce(&mut ReadOptions),
+    {
+        let mut opts = ReadOptions::default();
+        configure(&mut opts);
+        match self.db.get_pinned_opt(serialize(k)?, &opts)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Like `get`, reading only from memtables and the block cache, never
+    /// from the database's files on disk.
+    ///
+    /// This never waits on disk, so it is cheap for recently written or read
+    /// keys. But it returns `None` for a key whose value would have to be read
+    /// from disk, so `None` does not mean that `k` has no value.
+    pub fn get_memtable_only(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        let mut opts = ReadOptions::default();
+        // rocksdb 0.19 takes a `ReadTier` here but does not export it, so it
+        // cannot be named. It is `#[repr(i32)]`, and 1 is `BlockCache`.
+        // SAFETY: 1 is a valid discriminant, and the sizes are checked.
+        #[allow(clippy::missing_transmute_annotations)]
+        opts.set_read_tier(unsafe { std::mem::transmute::<i32, _>(1) });
+        match self.db.get_pinned_opt(serialize(k)?, &opts) {
+            Ok(Some(bytes)) => Ok(Some(deserialize(&bytes)?)),
+            Ok(None) => Ok(None),
+            // A value that is only on disk is reported as incomplete.
+            Err(e) if e.kind() == ErrorKind::Incomplete => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get the value for `k`, or if there is none, store the value produced by
+    /// `f` and return that.
+    ///
+    /// This is a separate read and write, not a transaction: if another writer
+    /// stores a value for `k` in between, that value is overwritten.
+    pub fn get_or_insert_with<F>(&self, k: KRef, f: F) -> Result<V, RustyRocksError>
+    where
+        F: FnOnce() -> VRef,
+    {
+        let key = serialize(k)?;
+        if let Some(bytes) = self.db.get_pinned(&key)? {
+            return deserialize(&bytes);
+        }
+        self.check_writable()?;
+        let value = serialize(f())?;
+        self.db.put(&key, &value)?;
+        deserialize(value.as_ref())
+    }
+
+    /// Hand the stored bytes for `k` to `f` without copying them out of rocksdb.
+    ///
+    /// The bytes are only valid for the duration of the call.
+    pub fn with_value<F, R>(&self, k: KRef, f: F) -> Result<Option<R>, RustyRocksError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        Ok(self.db.get_pinned(serialize(k)?)?.map(|bytes| f(&bytes)))
+    }
+
+    /// Read the value for each of `keys` in one batched call, in the same
+    /// order, with `default` in place of each missing value.
+    pub fn multi_get_or<I>(&self, keys: I, default: V) -> Result<Vec<V>, RustyRocksError>
+    where
+        I: IntoIterator<Item = KRef>,
+        V: Clone,
+    {
+        let serialized = keys
+            .into_iter()
+            .map(serialize)
+            .collect::<Result<Vec<_>, RustyRocksError>>()?;
+        self.db
+            .multi_get(serialized.iter().map(AsRef::as_ref))
+            .into_iter()
+            .map(|value| match value? {
+                Some(bytes) => deserialize(&bytes),
+                None => Ok(default.clone()),
+            })
+            .collect()
+    }
+
+    /// Read the value for each of `keys` in one batched call, returning each
+    /// key alongside its value, in the same order.
+    pub fn multi_get_pairs<I>(&self, keys: I) -> Result<Vec<(KRef, Option<V>)>, RustyRocksError>
+    where
+        I: IntoIterator<Item = KRef>,
+        KRef: Clone,
+    {
+        let keys: Vec<KRef> = keys.into_iter().collect();
+        let serialized = keys
+            .iter()
+            .map(|k| serialize(k.clone()))
+            .collect::<Result<Vec<_>, RustyRocksError>>()?;
+        let values = self.db.multi_get(serialized.iter().map(AsRef::as_ref));
+        keys.into_iter()
+            .zip(values)
+            .map(|(k, value)| match value? {
+                Some(bytes) => Ok((k, Some(deserialize(&bytes)?))),
+                None => Ok((k, None)),
+            })
+            .collect()
+    }
+
+    /// Check cheaply whether `k` might have a value, from the memtables, the
+    /// block cache and any bloom filters, without reading from disk.
+    ///
+    /// `false` means `k` definitely has no value. `true` may be a false
+    /// positive, so follow it up with `get` when it matters.
+    pub fn key_may_exist(&self, k: KRef) -> Result<bool, RustyRocksError> {
+        Ok(self.db.key_may_exist(serialize(k)?))
+    }
+
+    /// The length in bytes of the stored value for `k`, without copying or
+    /// deserializing it.
+    pub fn value_size(&self, k: KRef) -> Result<Option<usize>, RustyRocksError> {
+        Ok(self.db.get_pinned(serialize(k)?)?.map(|bytes| bytes.len()))
+    }
+
+    /// The stored bytes for `k`, without copying them out of rocksdb.
+    ///
+    /// Unlike `with_value`, the bytes can be held on to, for as long as the
+    /// returned guard lives.
+    pub fn get_raw(&self, k: KRef) -> Result<Option<PinnedValue<'_>>, RustyRocksError> {
+        Ok(self.db.get_pinned(serialize(k)?)?.map(PinnedValue::new))
+    }
+
+    /// Deserialize the value for `k` as a view borrowing from the stored bytes,
+    /// and hand it to `f`.
+    ///
+    /// Returning the borrow directly is not possible, as it only lives as long
+    /// as rocksdb keeps the value pinned.
+    pub fn get_borrowed<T, F, R>(&self, k: KRef, f: F) -> Result<Option<R>, RustyRocksError>
+    where
+        T: ?Sized,
+        for<'b> &'b T: BorrowDeserialize<'b>,
+        F: FnOnce(&T) -> R,
+    {
+        match self.db.get_pinned(serialize(k)?)? {
+            Some(bytes) => {
+                let view =
+                    <&T>::deserialize_borrowed(&bytes).map_err(RustyRocksError::deserialize)?;
+                Ok(Some(f(view)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn put(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        self.db.put(serialize(k)?, serialize(v)?)?;
+        Ok(())
+    }
+
+    /// Merge `v` into the value for `k` with the merge operator installed in
+    /// the options passed to `open_with_opts`.
+    ///
+    /// This fails if the database has no merge operator. `MergeableDB` sets
+    /// one up from the value type instead.
+    pub fn merge_raw(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        self.db.merge(serialize(k)?, serialize(v)?)?;
+        Ok(())
+    }
+
+    /// Like `put`, with `configure` applied to the write options first, e.g. to
+    /// call `set_sync(true)` for a durable write.
+    pub fn put_opt<F>(&self, k: KRef, v: VRef, configure: F) -> Result<(), RustyRocksError>
+    where
+        F: FnOnce(&mut WriteOptions),
+    {
+        self.check_writable()?;
+        let mut opts = WriteOptions::default();
+        configure(&mut opts);
+        self.db.put_opt(serialize(k)?, serialize(v)?, &opts)?;
+        Ok(())
+    }
+
+    /// Store `v` under `k` unless `k` already has a value, returning whether
+    /// it was stored.
+    ///
+    /// Like `get_or_insert_with`, this is a separate read and write: two
+    /// concurrent callers can both find `k` absent and both write. Use a
+    /// `TransactionalDB` where that matters.
+    pub fn put_if_absent(&self, k: KRef, v: VRef) -> Result<bool, RustyRocksError> {
+        self.check_writable()?;
+        let key = serialize(k)?;
+        if self.db.get_pinned(&key)?.is_some() {
+            return Ok(false);
+        }
+        self.db.put(&key, serialize(v)?)?;
+        Ok(true)
+    }
+
+    /// Store `v` under `k` unless it is already stored there, returning
+    /// whether it was written.
+    ///
+    /// Values are compared in their serialized form, so `V` need not be
+    /// comparable. The read and write are separate, as with `put_if_absent`.
+    pub fn put_if_changed(&self, k: KRef, v: VRef) -> Result<bool, RustyRocksError> {
+        self.check_writable()?;
+        let key = serialize(k)?;
+        let value = serialize(v)?;
+        if let Some(current) = self.db.get_pinned(&key)? {
+            if *current == *value.as_ref() {
+                return Ok(false);
+            }
+        }
+        self.db.put(&key, &value)?;
+        Ok(true)
+    }
+
+    pub fn delete(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        self.db.delete(serialize(k)?)?;
+        Ok(())
+    }
+
+    /// Write every pair in `pairs`, whose keys must already be in sorted
+    /// order, atomically in a single batch.
+    ///
+    /// Sorted keys spare rocksdb's memtable the work of placing each one. The
+    /// order is only checked in debug builds, where keys out of order panic;
+    /// use `checked_put_sorted` to check it always. For inputs too large to
+    /// hold in memory as one batch, write them to an `SstWriter` and ingest
+    /// that instead.
+    pub fn put_sorted<I>(&self, pairs: I) -> Result<(), RustyRocksError>
+    where
+        I: IntoIterator<Item = (KRef, VRef)>,
+    {
+        let result = self.write_pairs(pairs, cfg!(debug_assertions));
+        if let Err(RustyRocksError::KeysOutOfOrder { index }) = result {
+            panic!(
+                "put_sorted: key {} sorts before the key preceding it",
+                index
+            );
+        }
+        result
+    }
+
+    /// Like `put_sorted`, failing with `RustyRocksError::KeysOutOfOrder`,
+    /// and writing nothing, if the keys are not in sorted order.
+    pub fn checked_put_sorted<I>(&self, pairs: I) -> Result<(), RustyRocksError>
+    where
+        I: IntoIterator<Item = (KRef, VRef)>,
+    {
+        self.write_pairs(pairs, true)
+    }
+
+    /// Write every pair in `pairs`, in any order, atomically in a single
+    /// batch. The `Extend` impl for `&KeyValueDB` does the same, panicking
+    /// on failure.
+    pub fn try_extend<I>(&self, pairs: I) -> Result<(), RustyRocksError>
+    where
+        I: IntoIterator<Item = (KRef, VRef)>,
+    {
+        self.write_pairs(pairs, false)
+    }
+
+    fn write_pairs<I>(&self, pairs: I, check: bool) -> Result<(), RustyRocksError>
+    where
+        I: IntoIterator<Item = (KRef, VRef)>,
+    {
+        self.check_writable()?;
+        let mut batch = WriteBatch::default();
+        // The batch copies each pair, so the same buffers serve for all.
+        let (mut key, mut last, mut value) = (Vec::new(), Vec::new(), Vec::new());
+        for (index, (k, v)) in pairs.into_iter().enumerate() {
+            serialize_into(k, &mut key)?;
+            if check && index > 0 && key < last {
+                return Err(RustyRocksError::KeysOutOfOrder { index });
+            }
+            serialize_into(v, &mut value)?;
+            batch.put(&key, &value);
+            std::mem::swap(&mut key, &mut last);
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Delete `k`, returning the value it had.
+    ///
+    /// This is a separate read and delete, so a write to `k` between them is
+    /// deleted without being returned. `TransactionalDB::take` does both
+    /// atomically.
+    pub fn take(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        self.check_writable()?;
+        let key = serialize(k)?;
+        let value = match self.db.get_pinned(&key)? {
+            Some(bytes) => deserialize(&bytes)?,
+            None => return Ok(None),
+        };
+        self.db.delete(&key)?;
+        Ok(Some(value))
+    }
+
+    /// Delete `k` if it has a value and `pred` holds for it, returning whether
+    /// it was deleted.
+    ///
+    /// Like `put_if_absent`, this is a separate read and write: a concurrent
+    /// write between them can be deleted without `pred` having seen it. Use a
+    /// `TransactionalDB` where that matters.
+    pub fn delete_if<F>(&self, k: KRef, pred: F) -> Result<bool, RustyRocksError>
+    where
+        F: FnOnce(&V) -> bool,
+    {
+        self.check_writable()?;
+        let key = serialize(k)?;
+        let matches = match self.db.get_pinned(&key)? {
+            Some(bytes) => pred(&deserialize(&bytes)?),
+            None => false,
+        };
+        if matches {
+            self.db.delete(&key)?;
+        }
+        Ok(matches)
+    }
+
+    /// Like `delete`, with `configure` applied to the write options first.
+    pub fn delete_opt<F>(&self, k: KRef, configure: F) -> Result<(), RustyRocksError>
+    where
+        F: FnOnce(&mut WriteOptions),
+    {
+        self.check_writable()?;
+        let mut opts = WriteOptions::default();
+        configure(&mut opts);
+        self.db.delete_opt(serialize(k)?, &opts)?;
+        Ok(())
+    }
+
+    /// Delete every key from `start` up to but *not including* `end`.
+    ///
+    /// The range is compared on the serialized bytes, so it only matches the
+    /// natural order of the keys for order-preserving encodings.
+    pub fn delete_range(&self, start: KRef, end: KRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        let mut batch = WriteBatch::default();
+        batch.delete_range(serialize(start)?, serialize(end)?);
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Apply a `rocksdb::WriteBatch` built elsewhere, atomically.
+    ///
+    /// The batch holds raw bytes, so nothing checks that its keys and values
+    /// are encoded as this database's types; ones that are not will fail to
+    /// deserialize when read back.
+    pub fn write_raw(&self, batch: WriteBatch) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Start a batch of writes that can be read back before `write_indexed`
+    /// commits them.
+    pub fn indexed_batch(&self) -> Result<TypedIndexedBatch<'_, KRef, V, VRef>, RustyRocksError> {
+        self.check_writable()?;
+        Ok(TypedIndexedBatch::new(&self.db))
+    }
+
+    /// Apply the writes staged in `batch`, atomically.
+    pub fn write_indexed(
+        &self,
+        batch: TypedIndexedBatch<'_, KRef, V, VRef>,
+    ) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        self.db.write(batch.into_batch())?;
+        Ok(())
+    }
+
+    /// Delete every key starting with `prefix`, as a single range tombstone.
+    pub fn delete_prefix(&self, prefix: KRef) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        let start = serialize(prefix)?;
+        let end = match iter::prefix_successor(start.as_ref()) {
+            Some(end) => end,
+            // The prefix is all 0xff bytes, so every key after it has the
+            // prefix; end the range just past the last key.
+            None => {
+                let mut iter = self.db.raw_iterator();
+                iter.seek_to_last();
+                match iter.key() {
+                    Some(k) if k >= start.as_ref() => [k, &[0]].concat(),
+                    _ => return Ok(iter.status()?),
+                }
+            }
+        };
+        let mut batch = WriteBatch::default();
+        batch.delete_range(start.as_ref(), &end);
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Delete every key in the database.
+    ///
+    /// This writes a single range tombstone covering all the keys and then
+    /// compacts, so it stays cheap on large databases and frees their space.
+    pub fn clear(&self) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        let mut iter = self.db.raw_iterator();
+        iter.seek_to_first();
+        let first = match iter.key() {
+            Some(k) => k.to_vec(),
+            None => {
+                iter.status()?;
+                return Ok(());
+            }
+        };
+        iter.seek_to_last();
+        // The range end is exclusive, so extend it just past the last key.
+        let mut end = match iter.key() {
+            Some(k) => k.to_vec(),
+            None => {
+                iter.status()?;
+                return Ok(());
+            }
+        };
+        end.push(0);
+        drop(iter);
+
+        let mut batch = WriteBatch::default();
+        batch.delete_range(&first, &end);
+        self.db.write(batch)?;
+        self.db.compact_range(Some(first), Some(end));
+        Ok(())
+    }
+
+    /// Compact the keys from `start` to `end` (inclusive), dropping deleted and
+    /// overwritten entries. A bound of `None` leaves that end of the range open.
+    pub fn compact_range(
+        &self,
+        start: Option<KRef>,
+        end: Option<KRef>,
+    ) -> Result<(), RustyRocksError> {
+        let start = start.map(serialize).transpose()?;
+        let end = end.map(serialize).transpose()?;
+        self.db.compact_range(start, end);
+        Ok(())
+    }
+
+    /// Compact the whole database.
+    pub fn compact_all(&self) {
+        self.db.compact_range::<&[u8], &[u8]>(None, None);
+    }
+
+    /// Stop rocksdb compacting in the background until
+    /// `enable_auto_compactions`, e.g. to speed up a large import.
+    ///
+    /// Files pile up in level 0 meanwhile, slowing reads and eventually
+    /// writes, so call `compact_all` once the import is done.
+    pub fn disable_auto_compactions(&self) -> Result<(), RustyRocksError> {
+        self.set_auto_compactions(false)
+    }
+
+    /// Let rocksdb compact in the background again after
+    /// `disable_auto_compactions`.
+    pub fn enable_auto_compactions(&self) -> Result<(), RustyRocksError> {
+        self.set_auto_compactions(true)
+    }
+
+    fn set_auto_compactions(&self, enabled: bool) -> Result<(), RustyRocksError> {
+        let opts = [(
+            "disable_auto_compactions",
+            if enabled { "false" } else { "true" },
+        )];
+        self.db.set_options(&opts)?;
+        for name in &self.column_families {
+            if let Some(cf) = self.db.cf_handle(name) {
+                self.db.set_options_cf(cf, &opts)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the SST files at `paths`, as written by `SstWriter`, into the
+    /// database.
+    ///
+    /// The files are moved or linked into the database rather than copied
+    /// where possible, which is far faster than writing their entries one by
+    /// one. Their entries overwrite any existing values for the same keys.
+    pub fn ingest_sst_files<P: AsRef<Path>>(&self, paths: &[P]) -> Result<(), RustyRocksError> {
+        self.check_writable()?;
+        let paths: Vec<&Path> = paths.iter().map(AsRef::as_ref).collect();
+        self.db.ingest_external_file(paths)?;
+        Ok(())
+    }
+
+    /// Start a bulk load that commits a write batch every `batch_size` puts.
+    pub fn bulk_loader(
+        &self,
+        batch_size: usize,
+    ) -> Result<BulkLoader<'_, KRef, VRef>, RustyRocksError> {
+        self.check_writable()?;
+        Ok(BulkLoader::new(&self.db, batch_size))
+    }
+
+    /// Flush the database and add a new backup of it to `backup_dir`.
+    ///
+    /// Backups are incremental: files shared with earlier backups in the same
+    /// directory are not copied again. See `purge_old_backups` to limit how
+    /// many are retained.
+    pub fn backup<B: AsRef<Path>>(&self, backup_dir: B) -> Result<(), RustyRocksError> {
+        let mut engine = BackupEngine::open(&BackupEngineOptions::default(), backup_dir)?;
+        engine.create_new_backup_flush(&self.db, true)?;
+        Ok(())
+    }
+
+    /// Write a consistent copy of the database to `dir`, which can then be
+    /// opened as a database of its own.
+    ///
+    /// `dir` must not exist yet. SST files are hard-linked rather than copied
+    /// when `dir` is on the same filesystem, which makes this much cheaper
+    /// than `backup`.
+    pub fn create_checkpoint<D: AsRef<Path>>(&self, dir: D) -> Result<(), RustyRocksError> {
+        Checkpoint::new(&self.db)?.create_checkpoint(dir)?;
+        Ok(())
+    }
+
+    /// rocksdb's statistics counters and histograms, as a human-readable
+    /// report, if the database was opened with `open_with_stats`.
+    pub fn statistics(&self) -> Option<String> {
+        self.stats_opts.as_ref()?.get_statistics()
+    }
+
+    /// rocksdb's statistics as a map from name to value, or an empty map if
+    /// the database was not opened with `open_with_stats`.
+    ///
+    /// Tickers appear under their own name, such as
+    /// `rocksdb.number.keys.written`. Each histogram `h` appears as `h.p50`,
+    /// `h.p95`, `h.p99`, `h.p100`, `h.count` and `h.sum`.
+    pub fn stats_snapshot(&self) -> HashMap<String, f64> {
+        let mut stats = HashMap::new();
+        for line in self.statistics().as_deref().unwrap_or("").lines() {
+            let mut words = line.split_whitespace();
+            let name = match words.next() {
+                Some(name) => name,
+                None => continue,
+            };
+            // The rest of the line is `FIELD : value` triples; a ticker has
+            // just a `COUNT`.
+            let fields: Vec<&str> = words.collect();
+            let is_ticker = fields.len() == 3;
+            for field in fields.chunks_exact(3) {
+                if let (":", Ok(value)) = (field[1], field[2].parse::<f64>()) {
+                    let key = if is_ticker {
+                        name.to_owned()
+                    } else {
+                        format!("{}.{}", name, field[0].to_lowercase())
+                    };
+                    stats.insert(key, value);
+                }
+            }
+        }
+        stats
+    }
+
+    /// Read every entry with checksum verification, failing with
+    /// `RustyRocksError::Corruption` at the first block that does not match
+    /// its checksum.
+    ///
+    /// This reads the whole database from disk, bypassing the block cache,
+    /// so it takes as long as a full scan. Only flushed data has checksums;
+    /// `flush` first to check recent writes too. To see a failure, flip a
+    /// byte in the middle of one of the database's `.sst` files while it is
+    /// closed, then reopen and verify.
+    pub fn verify_checksums(&self) -> Result<(), RustyRocksError> {
+        let mut opts = ReadOptions::default();
+        opts.set_verify_checksums(true);
+        opts.fill_cache(false);
+        opts.set_total_order_seek(true);
+        let corruption = |e: rocksdb::Error| match e.kind() {
+            ErrorKind::Corruption => RustyRocksError::Corruption(e),
+            _ => e.into(),
+        };
+        let mut iter = self.db.raw_iterator_opt(opts);
+        iter.seek_to_first();
+        while iter.valid() {
+            iter.next();
+        }
+        iter.status().map_err(corruption)
+    }
+
+    /// Stop rocksdb's pending flushes and compactions, so that closing the
+    /// database does not wait for them, waiting for those already running to
+    /// finish if `wait` is set.
+    ///
+    /// Background work stays stopped: call this only just before dropping
+    /// the database. Writes made since the last flush are still in the
+    /// write-ahead log, so nothing is lost.
+    pub fn cancel_background_work(&self, wait: bool) {
+        self.db.cancel_all_background_work(wait);
+    }
+
+    /// Flush the memtables of every open column family to SST files.
+    ///
+    /// This flush is not atomic across column families, even with
+    /// `Options::set_atomic_flush`. rocksdb 0.19 goes through rocksdb's C
+    /// API, which can only flush one column family per call, and with atomic
+    /// flush on such a call flushes just that column family. So they are
+    /// flushed in turn, and a crash partway through leaves some flushed and
+    /// others not; with the write-ahead log enabled, reopening replays the
+    /// rest. Atomic flush does still apply to the flushes rocksdb starts
+    /// itself when a memtable fills, which cover every column family at
+    /// once.
+    pub fn flush(&self) -> Result<(), RustyRocksError> {
+        self.db.flush()?;
+        for name in &self.column_families {
+            if let Some(cf) = self.db.cf_handle(name) {
+                self.db.flush_cf(cf)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush the database and close it, returning any error from the flush.
+    ///
+    /// Dropping a `KeyValueDB` closes it too, but silently: a flush that
+    /// fails then, e.g. because the disk is full, goes unreported. The
+    /// database only closes once every clone of it is gone, so this just
+    /// flushes if other clones remain. Read-only databases have nothing to
+    /// flush.
+    pub fn close(self) -> Result<(), RustyRocksError> {
+        if !self.read_only {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Make every write so far durable: fsync the write-ahead log, then flush
+    /// the memtables to SST files, returning once both are on disk.
+    ///
+    /// This waits on disk twice and forces a flush of whatever is in the
+    /// memtables, however small, so calling it often produces many small
+    /// files and slows writes down considerably. Writing with `put_opt` and
+    /// `set_sync(true)` is the cheaper way to make individual writes durable.
+    pub fn sync(&self) -> Result<(), RustyRocksError> {
+        self.db.flush_wal(true)?;
+        self.flush()
+    }
+
+    /// Flush the write-ahead log, and also fsync it if `sync` is set.
+    pub fn flush_wal(&self, sync: bool) -> Result<(), RustyRocksError> {
+        self.db.flush_wal(sync)?;
+        Ok(())
+    }
+
+    /// An estimate of the number of keys in the database, read from rocksdb's
+    /// `rocksdb.estimate-num-keys` property.
+    ///
+    /// This is cheap but approximate: overwritten, deleted and merged keys can
+    /// be over- or under-counted until compaction.
+    pub fn approximate_len(&self) -> Result<u64, RustyRocksError> {
+        Ok(self
+            .db
+            .property_int_value(properties::ESTIMATE_NUM_KEYS)?
+            .unwrap_or(0))
+    }
+
+    /// Read the rocksdb property `name`, such as `properties::STATS`, or `None`
+    /// if there is no such property.
+    pub fn property_value(&self, name: &str) -> Result<Option<String>, RustyRocksError> {
+        Ok(self.db.property_value(name)?)
+    }
+
+    /// Read the rocksdb property `name` as an integer, or `None` if there is no
+    /// such property.
+    pub fn property_int_value(&self, name: &str) -> Result<Option<u64>, RustyRocksError> {
+        Ok(self.db.property_int_value(name)?)
+    }
+
+    /// Whether the database holds no keys at all.
+    ///
+    /// Unlike `approximate_len`, this is exact: it probes for a first key.
+    pub fn is_empty(&self) -> Result<bool, RustyRocksError> {
+        let mut iter = self.db.raw_iterator();
+        iter.seek_to_first();
+        if iter.valid() {
+            return Ok(false);
+        }
+        iter.status()?;
+        Ok(true)
+    }
+
+    /// The pair with the smallest key, if the database is not empty.
+    pub fn first(&self) -> Result<Option<(K, V)>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        let mut iter = self.db.raw_iterator();
+        iter.seek_to_first();
+        Self::decode_item(&iter)
+    }
+
+    /// The pair with the largest key, if the database is not empty.
+    pub fn last(&self) -> Result<Option<(K, V)>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        let mut iter = self.db.raw_iterator();
+        iter.seek_to_last();
+        Self::decode_item(&iter)
+    }
+
+    fn decode_item(iter: &DBRawIterator) -> Result<Option<(K, V)>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        match iter.item() {
+            Some((k, v)) => Ok(Some(deserialize_pair(k, v)?)),
+            None => {
+                iter.status()?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Iterate over all key-value pairs, in key order.
+    pub fn db_iter(&self) -> DBIter<'_, K, V>
+    where
+        K: Deserializable,
+    {
+        DBIter::new(&self.db)
+    }
+
+    /// Read up to `limit` key-value pairs in key order, starting just after
+    /// the key `after`, or from the first key if it is `None`.
+    ///
+    /// The second element is the cursor to pass as `after` for the next
+    /// page: the last key read, or `None` once there is nothing left. Pages
+    /// are read independently, so writes between them may or may not show
+    /// up in later pages, but no key is ever returned twice.
+    pub fn page(&self, after: Option<KRef>, limit: usize) -> Result<Page<K, V>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        let mut opts = ReadOptions::default();
+        opts.set_total_order_seek(true);
+        let mut iter = self.db.raw_iterator_opt(opts);
+        match after {
+            Some(after) => {
+                let after = serialize(after)?;
+                iter.seek(&after);
+                if iter.key() == Some(after.as_ref()) {
+                    iter.next();
+                }
+            }
+            None => iter.seek_to_first(),
+        }
+        let mut pairs = Vec::with_capacity(limit.min(1024));
+        let mut cursor = None;
+        while pairs.len() < limit {
+            match iter.item() {
+                Some((k, v)) => {
+                    pairs.push(deserialize_pair(k, v)?);
+                    cursor = Some(k.to_vec());
+                }
+                None => {
+                    iter.status()?;
+                    cursor = None;
+                    break;
+                }
+            }
+            iter.next();
+        }
+        let cursor = match cursor {
+            // A full page may have been the last; only hand out a cursor if
+            // there is more to read.
+            Some(k) if iter.valid() => Some(deserialize_key(&k)?),
+            _ => None,
+        };
+        Ok((pairs, cursor))
+    }
+
+    /// Collect the key-value pairs in key order for as long as `f` holds,
+    /// stopping at the first pair it rejects, which is left out.
+    ///
+    /// Nothing past that pair is read.
+    pub fn iter_while<F>(&self, mut f: F) -> Result<Vec<(K, V)>, RustyRocksError>
+    where
+        K: Deserializable,
+        F: FnMut(&K, &V) -> bool,
+    {
+        let mut pairs = Vec::new();
+        for kv in self.db_iter() {
+            let (k, v) = kv?;
+            if !f(&k, &v) {
+                break;
+            }
+            pairs.push((k, v));
+        }
+        Ok(pairs)
+    }
+
+    /// Iterate over all key-value pairs, in key order, with an iterator that
+    /// keeps the database open rather than borrowing this handle.
+    pub fn owned_iter(&self) -> OwnedDBIter<K, V>
+    where
+        K: Deserializable,
+    {
+        OwnedDBIter::new(Arc::clone(&self.db))
+    }
+
+    /// Like `db_iter`, with `configure` applied to the read options of the
+    /// iterator.
+    ///
+    /// Large scans can call `set_fill_cache(false)` here, so as not to evict
+    /// more useful blocks from the block cache. `configure` may be called more
+    /// than once.
+    pub fn db_iter_opt<F>(&self, configure: F) -> DBIter<'_, K, V>
+    where
+        K: Deserializable,
+        F: Fn(&mut ReadOptions),
+    {
+        DBIter::configured(&self.db, IteratorMode::Start, configure)
+    }
+
+    /// Like `db_iter`, tuned for a full scan of a database that is mostly not
+    /// in memory: each file is read ahead `readahead` bytes at a time, and
+    /// the blocks read are not added to the block cache.
+    ///
+    /// This is best for a single pass over cold data; on a database that fits
+    /// in the cache, plain `db_iter` is as fast.
+    ///
+    /// Reads are still synchronous. rocksdb can prefetch the next blocks
+    /// asynchronously with its `async_io` read option, but rocksdb 0.19 does
+    /// not expose it, so this scan waits on each readahead in turn.
+    pub fn db_iter_scan(&self, readahead: usize) -> DBIter<'_, K, V>
+    where
+        K: Deserializable,
+    {
+        self.db_iter_opt(|opts| {
+            opts.set_readahead_size(readahead);
+            opts.fill_cache(false);
+        })
+    }
+
+    /// Iterate as rocksdb's `mode` says: from the first key, from the last
+    /// key backwards, or from a serialized key in either direction.
+    ///
+    /// Build the key for `IteratorMode::From` with `serialize_key`.
+    pub fn db_iter_mode(&self, mode: IteratorMode) -> DBIter<'_, K, V>
+    where
+        K: Deserializable,
+    {
+        DBIter::with_mode(&self.db, mode)
+    }
+
+    /// Serialize `k` as it is stored, e.g. to pass to `IteratorMode::From`.
+    pub fn serialize_key(k: KRef) -> Result<Vec<u8>, RustyRocksError> {
+        Ok(serialize(k)?.as_ref().to_vec())
+    }
+
+    /// Iterate from `start` in the given direction: forwards from the first key
+    /// at or after `start`, or backwards from the last key at or before it.
+    ///
+    /// `start` need not exist in the database, which makes this suitable for
+    /// resuming a paginated scan.
+    pub fn db_iter_from(
+        &self,
+        start: KRef,
+        direction: Direction,
+    ) -> Result<DBIter<'_, K, V>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        let start = serialize(start)?;
+        Ok(DBIter::with_mode(
+            &self.db,
+            IteratorMode::From(start.as_ref(), direction),
+        ))
+    }
+
+    /// Iterate over the key-value pairs whose serialized key starts with the
+    /// serialized `prefix`, in key order.
+    ///
+    /// See `open_with_prefix` to make this cheaper with a prefix extractor.
+    pub fn db_iter_prefix(&self, prefix: KRef) -> Result<DBIter<'_, K, V>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        Ok(DBIter::with_prefix(&self.db, serialize(prefix)?.as_ref()))
+    }
+
+    /// Like `db_iter_prefix`, iterating in descending key order, e.g. to find
+    /// the latest entries under a prefix.
+    pub fn db_iter_prefix_rev(&self, prefix: KRef) -> Result<DBIter<'_, K, V>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        Ok(DBIter::with_prefix_rev(
+            &self.db,
+            serialize(prefix)?.as_ref(),
+        ))
+    }
+
+    /// Iterate over the key-value pairs under `prefix` like `db_iter_prefix`,
+    /// with each key stripped of the prefix and deserialized as an `S`.
+    ///
+    /// This suits hierarchical keys: with string keys, the prefix `"dir/"`
+    /// yields `"a"` for the key `"dir/a"`.
+    pub fn scan_prefix_suffixes<S: Deserializable>(
+        &self,
+        prefix: KRef,
+    ) -> Result<impl Iterator<Item = Result<(S, V), RustyRocksError>> + '_, RustyRocksError> {
+        let prefix = serialize(prefix)?;
+        let prefix = prefix.as_ref();
+        let len = prefix.len();
+        let iter = self.db.iterator_opt(
+            IteratorMode::From(prefix, Direction::Forward),
+            iter::prefix_bounds(prefix),
+        );
+        Ok(iter.map(move |kv| {
+            let (k, v) = kv?;
+            Ok((deserialize_key(&k[len..])?, deserialize_value(&k, &v)?))
+        }))
+    }
+
+    /// Iterate over the keys alone, in key order, without deserializing values.
+    pub fn db_keys(&self) -> DBIter<'_, K, V, KeysOnly>
+    where
+        K: Deserializable,
+    {
+        DBIter::new(&self.db)
+    }
+
+    /// Iterate over the values alone, in key order, without deserializing keys.
+    pub fn db_values(&self) -> DBIter<'_, K, V, ValuesOnly> {
+        DBIter::new(&self.db)
+    }
+
+    /// An async handle to the same database, for use from a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn to_async(&self) -> AsyncKeyValueDB<KRef, V, VRef, K> {
+        AsyncKeyValueDB::new(Arc::clone(&self.db), self.read_only)
+    }
+
+    /// The exact number of keys in the database.
+    ///
+    /// This iterates over every key, so it is much slower than
+    /// `approximate_len` on a large database.
+    pub fn count(&self) -> Result<usize, RustyRocksError> {
+        let mut n = 0;
+        for kv in self.db.iterator(IteratorMode::Start) {
+            kv?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    /// The number of keys starting with `prefix`.
+    ///
+    /// This seeks straight to the prefix and stops at the end of it, so only
+    /// the matching keys are read, and with a prefix extractor (see
+    /// `open_with_prefix`) the seek skips files whose prefix bloom filters
+    /// rule the prefix out. Nothing is deserialized.
+    pub fn count_prefix(&self, prefix: KRef) -> Result<usize, RustyRocksError> {
+        let prefix = serialize(prefix)?;
+        let mut iter = self
+            .db
+            .raw_iterator_opt(iter::prefix_bounds(prefix.as_ref()));
+        iter.seek(prefix.as_ref());
+        let mut n = 0;
+        while iter.valid() {
+            n += 1;
+            iter.next();
+        }
+        iter.status()?;
+        Ok(n)
+    }
+
+    /// The number of keys starting with each of `prefixes`, in the same
+    /// order, each counted as by `count_prefix`.
+    pub fn count_by_prefix(&self, prefixes: &[KRef]) -> Result<Vec<usize>, RustyRocksError>
+    where
+        KRef: Clone,
+    {
+        prefixes
+            .iter()
+            .map(|prefix| self.count_prefix(prefix.clone()))
+            .collect()
+    }
+
+    /// Collect every key-value pair into a `Vec`, in key order, stopping at the
+    /// first that fails to read or deserialize.
+    ///
+    /// This is meant for small databases, as it holds everything in memory.
+    pub fn entries(&self) -> Result<Vec<(K, V)>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        // The estimate can be far off, so it only sizes the first allocation
+        // up to a point.
+        let estimate = self.approximate_len().unwrap_or(0).min(1 << 16);
+        let mut entries = Vec::with_capacity(estimate as usize);
+        for kv in self.db_iter() {
+            entries.push(kv?);
+        }
+        Ok(entries)
+    }
+
+    /// Fold `f` over every value in key order, stopping at the first value
+    /// that fails to read or deserialize.
+    pub fn fold_values<B, F>(&self, init: B, mut f: F) -> Result<B, RustyRocksError>
+    where
+        F: FnMut(B, V) -> B,
+    {
+        self.db_values().try_fold(init, |acc, v| Ok(f(acc, v?)))
+    }
+
+    /// Delete every entry for which `f` returns false, and return how many
+    /// were deleted.
+    ///
+    /// The keys to delete are collected in a full scan before any are
+    /// deleted, so they are all held in memory at once. Nothing is deleted if
+    /// an entry fails to read or deserialize.
+    pub fn retain<F>(&self, f: F) -> Result<usize, RustyRocksError>
+    where
+        K: Deserializable,
+        F: Fn(&K, &V) -> bool,
+    {
+        self.check_writable()?;
+        let mut batch = WriteBatch::default();
+        for kv in self.db.iterator(IteratorMode::Start) {
+            let (k, v) = kv?;
+            let (key, value) = deserialize_pair(&k, &v)?;
+            if !f(&key, &value) {
+                batch.delete(k);
+            }
+        }
+        let deleted = batch.len();
+        self.db.write(batch)?;
+        Ok(deleted)
+    }
+
+    /// Take a point-in-time snapshot of the database for consistent reads.
+    pub fn snapshot(&self) -> Snapshot<'_, KRef, V, K> {
+        Snapshot::new(self.db.snapshot())
+    }
+}
@@ -0,0 +1,839 @@
+use std::convert::TryInto;
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use rocksdb::{
+    BlockBasedOptions, DBCompactionStyle, DBCompressionType, LogLevel, MemtableFactory,
+    MergeOperands, Options, SliceTransform, UniversalCompactOptions, WriteBatch, WriteOptions,
+};
+
+use crate::{
+    deserialize, serialize, BorrowDeserialize, DBIter, Deserializable, KeyRef, KeyValueDB,
+    RustyRocksError, TrySerializable,
+};
+
+/// What the merge operator should do with bytes that failed to deserialize.
+pub enum MergePolicy<V> {
+    /// Drop the bytes and keep merging the rest.
+    Skip,
+    /// Merge this value in their place.
+    Use(V),
+    /// Stop merging and leave the existing value unchanged.
+    Abort,
+}
+
+/// A value that rocksdb can combine with an associative merge operator.
+///
+/// Merges need not be commutative: values are always combined in the order
+/// they were written. The existing value, if any, comes first, and each
+/// later operand is merged into the result of the earlier ones, so
+/// `a.merge(b)` always has `b` written after `a`. Partial merges combine
+/// runs of adjacent operands in the same order.
+pub trait AssociateMergeable: Sized + Deserializable {
+    /// Combine `other`, which was written after `self`, into `self`. This
+    /// must be associative.
+    fn merge(&mut self, other: Self);
+
+    /// Combine two operands that are being merged ahead of time, before the
+    /// existing value they will be merged into is known.
+    ///
+    /// rocksdb does this during compaction to collapse runs of operands. The
+    /// default is `merge`, which is right for most types; override it where
+    /// operands combine differently from an operand and a full value.
+    fn partial_merge(&mut self, other: Self) {
+        self.merge(other);
+    }
+
+    /// Tidy the result of a full merge before it is stored.
+    ///
+    /// A full merge always starts from the existing value or from nothing,
+    /// so this can resolve anything `partial_merge` had to leave pending. It
+    /// runs even when there was only one operand to merge. The default
+    /// returns the value unchanged.
+    fn settle(self) -> Self {
+        self
+    }
+
+    /// Serialize the merged value for storage.
+    ///
+    /// This consumes the value, so types that already hold their serialized
+    /// form can return that buffer without copying it.
+    fn into_bytes(self) -> Vec<u8>;
+
+    /// Decide what to do when the existing value or an operand stored under
+    /// `key` fails to deserialize.
+    fn handle_deser_error(key: &[u8], bytes: &[u8], err: Self::Error) -> MergePolicy<Self>;
+}
+
+/// Bytes that failed to deserialize during a merge, as reported to the
+/// handler installed with `MergeableDB::set_error_handler`.
+#[derive(Debug)]
+pub struct MergeError<'a> {
+    /// The key being merged.
+    pub key: &'a [u8],
+    /// The existing value or operand that failed to deserialize.
+    pub bytes: &'a [u8],
+    pub error: &'a (dyn StdError + 'static),
+}
+
+type ErrorHandler = Box<dyn Fn(&MergeError<'_>) + Send + Sync>;
+
+#[derive(Default)]
+struct Hooks {
+    handler: RwLock<Option<ErrorHandler>>,
+    errors: AtomicU64,
+}
+
+// Shared with the merge operator, which outlives any borrow of the database.
+type ErrorHook = Arc<Hooks>;
+
+fn report(hook: &ErrorHook, err: &MergeError<'_>) {
+    hook.errors.fetch_add(1, Ordering::Relaxed);
+    // A handler that panicked has already done all the reporting it is going to.
+    if let Ok(guard) = hook.handler.read() {
+        if let Some(handler) = guard.as_ref() {
+            handler(err);
+        }
+    }
+}
+
+// Partial merges pass no existing value, combine with `partial_merge` and
+// are not settled.
+fn merge<V: AssociateMergeable>(
+    key: &[u8],
+    existing_val: Option<&[u8]>,
+    operands: &MergeOperands,
+    hook: &ErrorHook,
+    combine: &dyn Fn(&mut V, V),
+    full: bool,
+) -> Option<Vec<u8>> {
+    let mut merged: Option<V> = None;
+    // With nothing to fall back on, aborting tells rocksdb the merge failed.
+    // A failed partial merge is harmless: rocksdb keeps the operands as they
+    // are.
+    let abort = || existing_val.map(<[u8]>::to_vec);
+
+    // rocksdb passes operands oldest first, so this folds in write order.
+    for bytes in existing_val.into_iter().chain(operands) {
+        let value = match V::deserialize(bytes) {
+            Ok(v) => v,
+            Err(e) => {
+                report(
+                    hook,
+                    &MergeError {
+                        key,
+                        bytes,
+                        error: &e,
+                    },
+                );
+                match V::handle_deser_error(key, bytes, e) {
+                    MergePolicy::Skip => continue,
+                    MergePolicy::Use(v) => v,
+                    MergePolicy::Abort => return abort(),
+                }
+            }
+        };
+        match merged {
+            Some(ref mut m) => combine(m, value),
+            None => merged = Some(value),
+        }
+    }
+
+    let merged = if full { merged.map(V::settle) } else { merged };
+    merged.map(V::into_bytes)
+}
+
+const DEFAULT_MERGE_NAME: &str = "rustyrocks merge";
+
+// How many operands `extend_merge` writes at a time.
+const EXTEND_BATCH_SIZE: usize = 1024;
+
+type Reducer<V> = Arc<dyn Fn(&mut V, V) + Send + Sync>;
+
+// With no reducer, values combine with `V`'s `merge` and `partial_merge`.
+fn install_merge_operator<V: AssociateMergeable + 'static>(
+    opts: &mut Options,
+    name: &str,
+    reducer: Option<Reducer<V>>,
+) -> ErrorHook {
+    let hook = ErrorHook::default();
+    let full_hook = Arc::clone(&hook);
+    let partial_hook = Arc::clone(&hook);
+    let (full, partial): (Reducer<V>, Reducer<V>) = match reducer {
+        Some(reducer) => (Arc::clone(&reducer), reducer),
+        None => (Arc::new(V::merge), Arc::new(V::partial_merge)),
+    };
+    opts.set_merge_operator(
+        name,
+        move |key, existing_val, operands| {
+            merge::<V>(key, existing_val, operands, &full_hook, &*full, true)
+        },
+        move |key, existing_val, operands| {
+            merge::<V>(key, existing_val, operands, &partial_hook, &*partial, false)
+        },
+    );
+    hook
+}
+
+// The merge operator name rocksdb recorded in the latest options file for the
+// default column family. rocksdb records it but does not check it on opening.
+// Anything that stops it being read, including there being no database yet,
+// is left for rocksdb to report, or not, when the database is opened.
+fn recorded_merge_operator(path: &Path) -> Option<String> {
+    let latest = fs::read_dir(path)
+        .ok()?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            let number: u64 = name.strip_prefix("OPTIONS-")?.parse().ok()?;
+            Some((number, name))
+        })
+        .max()?;
+    let options = fs::read_to_string(path.join(latest.1)).ok()?;
+    let mut in_default = false;
+    for line in options.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_default = line == "[CFOptions \"default\"]";
+        } else if in_default {
+            if let Some(name) = line.strip_prefix("merge_operator=") {
+                return match name {
+                    "nullptr" => None,
+                    name => Some(name.to_owned()),
+                };
+            }
+        }
+    }
+    None
+}
+
+/// A `KeyValueDB` whose values are combined with a merge operator built from
+/// `V`'s `AssociateMergeable` implementation.
+pub struct MergeableDB<KRef, V, VRef, K = <KRef as KeyRef>::Owned> {
+    kv: KeyValueDB<KRef, V, VRef, K>,
+    hook: ErrorHook,
+}
+
+impl<KRef, V, VRef, K> fmt::Debug for MergeableDB<KRef, V, VRef, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MergeableDB").field("kv", &self.kv).finish()
+    }
+}
+
+impl<KRef, V, VRef, K> MergeableDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: AssociateMergeable + 'static,
+    VRef: TrySerializable,
+{
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        Self::with_name(path, DEFAULT_MERGE_NAME)
+    }
+
+    /// Open the database at `path`, registering the merge operator as `name`.
+    ///
+    /// A database must always be reopened with the name it was created with;
+    /// any other fails with `RustyRocksError::MergeOperatorMismatch`.
+    pub fn with_name<P: AsRef<Path>>(path: P, name: &str) -> Result<Self, RustyRocksError> {
+        MergeableDBBuilder::new().name(name).open(path)
+    }
+
+    /// Open the database at `path`, combining values with `reducer` rather
+    /// than `AssociateMergeable::merge`. See `MergeableDBBuilder::reducer`.
+    pub fn with_reducer<P, F>(path: P, reducer: F) -> Result<Self, RustyRocksError>
+    where
+        P: AsRef<Path>,
+        F: Fn(&mut V, V) + Send + Sync + 'static,
+    {
+        MergeableDBBuilder::new().reducer(reducer).open(path)
+    }
+
+    /// Start configuring how the database is opened.
+    pub fn builder() -> MergeableDBBuilder<KRef, V, VRef, K> {
+        MergeableDBBuilder::new()
+    }
+
+    /// Open an existing database at `path` without write access; see
+    /// `KeyValueDB::open_read_only`.
+    ///
+    /// The merge operator takes the name recorded in the database's options,
+    /// so databases opened with `with_name` can be read back as well. A
+    /// database opened with a custom reducer still reads with `V::merge`.
+    pub fn open_read_only<P: AsRef<Path>>(
+        path: P,
+        error_if_log_file_exist: bool,
+    ) -> Result<Self, RustyRocksError> {
+        let path = path.as_ref();
+        let name = recorded_merge_operator(path);
+        let mut opts = Options::default();
+        let hook = install_merge_operator::<V>(
+            &mut opts,
+            name.as_deref().unwrap_or(DEFAULT_MERGE_NAME),
+            None,
+        );
+        Ok(MergeableDB {
+            kv: KeyValueDB::open_read_only_with_opts(path, opts, error_if_log_file_exist)?,
+            hook,
+        })
+    }
+
+    /// Call `handler` whenever the merge operator finds bytes that fail to
+    /// deserialize, before `AssociateMergeable::handle_deser_error` decides
+    /// what to do with them. By default nothing is called.
+    ///
+    /// Merges run when values are read and during compaction, possibly on
+    /// rocksdb's background threads.
+    pub fn set_error_handler<F>(&self, handler: F)
+    where
+        F: Fn(&MergeError<'_>) + Send + Sync + 'static,
+    {
+        let mut guard = self.hook.handler.write().unwrap_or_else(|e| e.into_inner());
+        *guard = Some(Box::new(handler));
+    }
+
+    /// How many times the merge operator has found bytes that fail to
+    /// deserialize since the database was opened.
+    ///
+    /// Every merge that meets bad bytes counts, so a corrupt operand read
+    /// several times before compaction rewrites it is counted each time.
+    pub fn merge_error_count(&self) -> u64 {
+        self.hook.errors.load(Ordering::Relaxed)
+    }
+
+    pub fn get(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        self.kv.get(k)
+    }
+
+    /// Hand the fully merged bytes for `k` to `f` without copying them out of
+    /// rocksdb. See `KeyValueDB::with_value`.
+    pub fn with_value<F, R>(&self, k: KRef, f: F) -> Result<Option<R>, RustyRocksError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.kv.with_value(k, f)
+    }
+
+    /// Deserialize the merged value for `k` as a borrowed view and hand it to
+    /// `f`. See `KeyValueDB::get_borrowed`.
+    pub fn get_borrowed<T, F, R>(&self, k: KRef, f: F) -> Result<Option<R>, RustyRocksError>
+    where
+        T: ?Sized,
+        for<'b> &'b T: BorrowDeserialize<'b>,
+        F: FnOnce(&T) -> R,
+    {
+        self.kv.get_borrowed(k, f)
+    }
+
+    pub fn put(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.kv.put(k, v)
+    }
+
+    pub fn merge(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.kv.check_writable()?;
+        self.kv.db.merge(serialize(k)?, serialize(v)?)?;
+        Ok(())
+    }
+
+    /// Merge `v` into the value for `k`, and return the merged value.
+    ///
+    /// The merge and the read are separate, so the value returned also
+    /// includes any merges made into `k` by other writers in between. It is
+    /// only `None` if another writer deleted `k` in between.
+    pub fn merge_and_get(&self, k: KRef, v: VRef) -> Result<Option<V>, RustyRocksError> {
+        self.kv.check_writable()?;
+        let key = serialize(k)?;
+        self.kv.db.merge(&key, serialize(v)?)?;
+        match self.kv.db.get_pinned(&key)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Merge every `(key, value)` pair in `ops` in a single atomic write.
+    ///
+    /// Nothing is written if any pair fails to serialize.
+    pub fn merge_batch<I>(&self, ops: I) -> Result<(), RustyRocksError>
+    where
+        I: IntoIterator<Item = (KRef, VRef)>,
+    {
+        self.kv.check_writable()?;
+        let mut batch = WriteBatch::default();
+        for (k, v) in ops {
+            batch.merge(serialize(k)?, serialize(v)?);
+        }
+        self.kv.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Merge every `(key, value)` pair in `ops`, for seeding the database from
+    /// an iterator of any length.
+    ///
+    /// Unlike `merge_batch`, the pairs are written in batches of a bounded
+    /// size, so they need not all fit in memory, but the write is not atomic:
+    /// an error leaves the batches before it merged.
+    pub fn extend_merge<I>(&self, ops: I) -> Result<(), RustyRocksError>
+    where
+        I: IntoIterator<Item = (KRef, VRef)>,
+    {
+        self.kv.check_writable()?;
+        let mut batch = WriteBatch::default();
+        for (k, v) in ops {
+            batch.merge(serialize(k)?, serialize(v)?);
+            if batch.len() >= EXTEND_BATCH_SIZE {
+                self.kv.db.write(std::mem::take(&mut batch))?;
+            }
+        }
+        if !batch.is_empty() {
+            self.kv.db.write(batch)?;
+        }
+        Ok(())
+    }
+
+    /// Merge an owned value, serialized with `AssociateMergeable::into_bytes`
+    /// rather than through `VRef`.
+    pub fn merge_owned(&self, k: KRef, v: V) -> Result<(), RustyRocksError> {
+        self.kv.check_writable()?;
+        self.kv.db.merge(serialize(k)?, v.into_bytes())?;
+        Ok(())
+    }
+
+    /// Merge in the delta `f` computes from the current value of `k`.
+    ///
+    /// This is not a transaction: another writer can merge into `k` between
+    /// the read and the merge. Because the delta is merged rather than put,
+    /// such a write is still combined with it instead of being lost, so this
+    /// is only as safe under races as `V`'s merge is associative. Deltas that
+    /// depend on the exact current value can still be computed from a stale
+    /// one.
+    pub fn update<F>(&self, k: KRef, f: F) -> Result<(), RustyRocksError>
+    where
+        F: FnOnce(Option<V>) -> V,
+    {
+        self.kv.check_writable()?;
+        let key = serialize(k)?;
+        let current = match self.kv.db.get_pinned(&key)? {
+            Some(bytes) => Some(deserialize(&bytes)?),
+            None => None,
+        };
+        self.kv.db.merge(&key, f(current).into_bytes())?;
+        Ok(())
+    }
+
+    /// Like `merge`, with `configure` applied to the write options first.
+    pub fn merge_opt<F>(&self, k: KRef, v: VRef, configure: F) -> Result<(), RustyRocksError>
+    where
+        F: FnOnce(&mut WriteOptions),
+    {
+        self.kv.check_writable()?;
+        let mut opts = WriteOptions::default();
+        configure(&mut opts);
+        self.kv.db.merge_opt(serialize(k)?, serialize(v)?, &opts)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.kv.delete(k)
+    }
+
+    /// Read the merged value for `k` in the column family `cf`, which must
+    /// have been opened with `MergeableDBBuilder::column_families`.
+    pub fn get_cf(&self, cf: &str, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        self.kv.get_cf(cf, k)
+    }
+
+    pub fn put_cf(&self, cf: &str, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.kv.put_cf(cf, k, v)
+    }
+
+    pub fn merge_cf(&self, cf: &str, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.kv.check_writable()?;
+        self.kv
+            .db
+            .merge_cf(self.kv.cf_handle(cf)?, serialize(k)?, serialize(v)?)?;
+        Ok(())
+    }
+
+    /// Flush every open column family; see `KeyValueDB::flush`.
+    pub fn flush(&self) -> Result<(), RustyRocksError> {
+        self.kv.flush()
+    }
+
+    /// Flush the database and close it, as `KeyValueDB::close` does.
+    pub fn close(self) -> Result<(), RustyRocksError> {
+        self.kv.close()
+    }
+
+    /// An async handle to the same database, for use from a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn to_async(&self) -> crate::AsyncKeyValueDB<KRef, V, VRef, K> {
+        self.kv.to_async()
+    }
+
+    pub fn db_iter(&self) -> DBIter<'_, K, V>
+    where
+        K: Deserializable,
+    {
+        self.kv.db_iter()
+    }
+}
+
+// The largest dictionary `zstd_max_train_bytes` trains.
+const DICTIONARY_BYTES: i32 = 16 * 1024;
+
+/// Configures how a `MergeableDB` is opened.
+///
+/// Created by `MergeableDB::builder`. By default the database is created if
+/// it is missing, as with `MergeableDB::new`.
+pub struct MergeableDBBuilder<KRef, V, VRef, K = <KRef as KeyRef>::Owned> {
+    opts: Options,
+    name: String,
+    reducer: Option<Reducer<V>>,
+    // Installed on `opts` when opening, as rocksdb copies it then.
+    table_opts: Option<BlockBasedOptions>,
+    // Options the rocksdb crate has no setter for, set on the open database.
+    dynamic_opts: Vec<(&'static str, String)>,
+    // Created when opening, as rocksdb only creates its last component.
+    wal_dir: Option<PathBuf>,
+    column_families: Vec<String>,
+    compact_on_open: bool,
+    phantom: PhantomData<(KRef, V, VRef, K)>,
+}
+
+impl<KRef, V, VRef, K> MergeableDBBuilder<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: AssociateMergeable + 'static,
+    VRef: TrySerializable,
+{
+    pub fn new() -> Self {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        MergeableDBBuilder {
+            opts,
+            name: DEFAULT_MERGE_NAME.to_owned(),
+            reducer: None,
+            table_opts: None,
+            dynamic_opts: Vec::new(),
+            wal_dir: None,
+            column_families: Vec::new(),
+            compact_on_open: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Register the merge operator as `name`.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    /// Combine values with `reducer` instead of `V`'s `merge` and
+    /// `partial_merge`, for both full and partial merges.
+    ///
+    /// Like `merge`, `reducer` must be associative. Opening the same database
+    /// with different reducers combines its values inconsistently.
+    pub fn reducer<F>(mut self, reducer: F) -> Self
+    where
+        F: Fn(&mut V, V) + Send + Sync + 'static,
+    {
+        self.reducer = Some(Arc::new(reducer));
+        self
+    }
+
+    /// Create the database if it does not exist yet.
+    pub fn create_if_missing(mut self, create: bool) -> Self {
+        self.opts.create_if_missing(create);
+        self
+    }
+
+    /// Fail to open if the database already exists.
+    pub fn error_if_exists(mut self, error: bool) -> Self {
+        self.opts.set_error_if_exists(error);
+        self
+    }
+
+    /// Open the column families named in `cfs` as well as the default one,
+    /// creating any that are missing. Every column family gets the same
+    /// options and merge operator.
+    pub fn column_families<I, N>(mut self, cfs: I) -> Self
+    where
+        I: IntoIterator<Item = N>,
+        N: AsRef<str>,
+    {
+        self.column_families
+            .extend(cfs.into_iter().map(|n| n.as_ref().to_owned()));
+        self
+    }
+
+    /// Flush every column family together whenever rocksdb flushes one
+    /// itself, so that with the write-ahead log disabled, writes to several
+    /// column families still survive a crash together.
+    ///
+    /// This does not cover `MergeableDB::flush`; see `KeyValueDB::flush`.
+    pub fn atomic_flush(mut self, atomic: bool) -> Self {
+        self.opts.set_atomic_flush(atomic);
+        self
+    }
+
+    /// Flush the memtables backing the oldest write-ahead log once the logs
+    /// reach `size` bytes in total, so the log can be dropped.
+    pub fn max_total_wal_size(mut self, size: u64) -> Self {
+        self.opts.set_max_total_wal_size(size);
+        self
+    }
+
+    /// Keep up to `num` finished write-ahead logs around to be reused, rather
+    /// than deleting them and allocating new ones.
+    pub fn recycle_log_file_num(mut self, num: usize) -> Self {
+        self.opts.set_recycle_log_file_num(num);
+        self
+    }
+
+    /// Keep at most `num` of rocksdb's informational log files.
+    pub fn keep_log_file_num(mut self, num: usize) -> Self {
+        self.opts.set_keep_log_file_num(num);
+        self
+    }
+
+    /// Keep at most `num` files open at once, or any number if `num` is -1,
+    /// which is the default.
+    pub fn max_open_files(mut self, num: i32) -> Self {
+        self.opts.set_max_open_files(num);
+        self
+    }
+
+    /// Run at most `jobs` flushes and compactions at once.
+    pub fn max_background_jobs(mut self, jobs: i32) -> Self {
+        self.opts.set_max_background_jobs(jobs);
+        self
+    }
+
+    /// Hold at most `num` memtables in memory, counting those waiting to be
+    /// flushed; writes stall once they are all full.
+    pub fn max_write_buffer_number(mut self, num: i32) -> Self {
+        self.opts.set_max_write_buffer_number(num);
+        self
+    }
+
+    /// Cap the memory used by this database's memtables, across all its
+    /// column families, at `bytes`; rocksdb flushes early to stay under it.
+    ///
+    /// rocksdb can also share one such cap between several databases with a
+    /// `WriteBufferManager`, but rocksdb 0.19 does not expose it, so to bound
+    /// memory across databases give each of them its share with this.
+    pub fn db_write_buffer_size(mut self, bytes: usize) -> Self {
+        self.opts.set_db_write_buffer_size(bytes);
+        self
+    }
+
+    /// Limit flushes and compactions to writing `bytes_per_sec` bytes a
+    /// second between them, so that they cannot starve reads and writes of
+    /// disk bandwidth.
+    ///
+    /// Writes still stall if background work falls too far behind, so a
+    /// limit below the sustained write rate slows writes down to it.
+    pub fn rate_limit_bytes_per_sec(mut self, bytes_per_sec: i64) -> Self {
+        // rocksdb's defaults for the refill period and fairness.
+        self.opts.set_ratelimiter(bytes_per_sec, 100_000, 10);
+        self
+    }
+
+    /// Write rocksdb's informational log at `level` and above. The default is
+    /// `LogLevel::Info`.
+    pub fn log_level(mut self, level: LogLevel) -> Self {
+        self.opts.set_log_level(level);
+        self
+    }
+
+    /// Write rocksdb's informational log to `dir` rather than the database
+    /// directory.
+    pub fn log_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.opts.set_db_log_dir(dir);
+        self
+    }
+
+    /// Write the write-ahead log to `dir` rather than the database directory,
+    /// for instance to put it on a separate, faster disk. `dir` is created
+    /// when opening if it does not exist.
+    ///
+    /// Reopen the database with the same `wal_dir`, or writes not yet flushed
+    /// from the log are lost.
+    pub fn wal_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.opts.set_wal_dir(&dir);
+        self.wal_dir = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Organize files with the compaction `style`. The default is
+    /// `DBCompactionStyle::Level`.
+    ///
+    /// `DBCompactionStyle::Universal` rewrites data fewer times, which suits
+    /// write-heavy workloads that rarely delete, at the cost of needing up to
+    /// twice the space during compactions.
+    pub fn compaction_style(mut self, style: DBCompactionStyle) -> Self {
+        self.opts.set_compaction_style(style);
+        self
+    }
+
+    /// Tune universal compaction with `options`; see `compaction_style`.
+    pub fn universal_compaction_options(mut self, options: &UniversalCompactOptions) -> Self {
+        self.opts.set_universal_compaction_options(options);
+        self
+    }
+
+    /// Compress the bottommost level, which holds most of the data once the
+    /// database has been compacted, with `compression` rather than the
+    /// compression used for the other levels.
+    pub fn bottommost_compression(mut self, compression: DBCompressionType) -> Self {
+        self.opts.set_bottommost_compression_type(compression);
+        self
+    }
+
+    /// Compress each bottommost file with a dictionary of up to 16KiB,
+    /// trained by zstd on up to `bytes` of samples from the file.
+    ///
+    /// This suits many small, similar values, which compress poorly on their
+    /// own. It only has an effect with `bottommost_compression` set to
+    /// `DBCompressionType::Zstd`.
+    pub fn zstd_max_train_bytes(mut self, bytes: usize) -> Self {
+        // rocksdb's defaults for the window bits and level.
+        self.opts
+            .set_bottommost_compression_options(-14, 32767, 0, DICTIONARY_BYTES, true);
+        self.opts
+            .set_bottommost_zstd_max_train_bytes(bytes.try_into().unwrap_or(i32::MAX), true);
+        self
+    }
+
+    /// Use the first `len` bytes of each serialized key as its prefix, as with
+    /// `KeyValueDB::open_with_prefix`.
+    pub fn prefix_len(mut self, len: usize) -> Self {
+        self.opts
+            .set_prefix_extractor(SliceTransform::create_fixed_prefix(len));
+        self
+    }
+
+    /// Hold memtable entries in a hash table of skiplists, one per key
+    /// prefix, instead of a single skiplist.
+    ///
+    /// This speeds up writes and seeks within a prefix for workloads that
+    /// mostly iterate by prefix, at the cost of slower iteration across
+    /// prefixes. It hashes on the prefix extractor, so it needs `prefix_len`
+    /// set too; without one, rocksdb falls back to the default skiplist.
+    /// It does not support concurrent memtable writes, so those are turned
+    /// off.
+    pub fn hash_skip_list_memtable(mut self, bucket_count: usize) -> Self {
+        self.opts
+            .set_memtable_factory(MemtableFactory::HashSkipList {
+                bucket_count,
+                height: 4,
+                branching_factor: 4,
+            });
+        self.opts.set_allow_concurrent_memtable_write(false);
+        self
+    }
+
+    /// Store SST data in blocks of about `size` bytes, rather than rocksdb's
+    /// default of 4KiB.
+    ///
+    /// Larger blocks compress better and shrink the index, while point reads
+    /// must read and decompress a whole block.
+    pub fn block_size(mut self, size: usize) -> Self {
+        self.table_opts
+            .get_or_insert_with(BlockBasedOptions::default)
+            .set_block_size(size);
+        self
+    }
+
+    /// Add a bloom filter of `bits_per_key` bits per key to each SST file, so
+    /// reads of absent keys can mostly skip the file.
+    ///
+    /// Ten bits per key gives about a 1% false positive rate.
+    pub fn bloom_filter_bits(mut self, bits_per_key: f64) -> Self {
+        self.table_opts
+            .get_or_insert_with(BlockBasedOptions::default)
+            .set_bloom_filter(bits_per_key, false);
+        self
+    }
+
+    /// Recompact SST files once their data is `seconds` old, so that
+    /// compaction filters and deletes eventually reach even data that no
+    /// other compaction would.
+    pub fn periodic_compaction_seconds(mut self, seconds: u64) -> Self {
+        self.dynamic_opts
+            .push(("periodic_compaction_seconds", seconds.to_string()));
+        self
+    }
+
+    /// Compact SST files holding data older than `seconds` down the levels
+    /// to the bottom one, where deletes and overwrites of it are dropped.
+    ///
+    /// Unlike `TtlDB`, this does not expire the values themselves.
+    pub fn sst_ttl_seconds(mut self, seconds: u64) -> Self {
+        self.dynamic_opts.push(("ttl", seconds.to_string()));
+        self
+    }
+
+    /// Compact the whole database once it is open, before `open` returns,
+    /// to reclaim the space taken by earlier deletes and overwrites.
+    ///
+    /// This rewrites every file, so it can make opening a large database
+    /// slow.
+    pub fn compact_on_open(mut self, compact: bool) -> Self {
+        self.compact_on_open = compact;
+        self
+    }
+
+    pub fn open<P: AsRef<Path>>(
+        mut self,
+        path: P,
+    ) -> Result<MergeableDB<KRef, V, VRef, K>, RustyRocksError> {
+        let path = path.as_ref();
+        if let Some(found) = recorded_merge_operator(path) {
+            if found != self.name {
+                return Err(RustyRocksError::MergeOperatorMismatch {
+                    expected: self.name,
+                    found,
+                });
+            }
+        }
+        if let Some(dir) = &self.wal_dir {
+            fs::create_dir_all(dir)?;
+        }
+        if let Some(table_opts) = &self.table_opts {
+            self.opts.set_block_based_table_factory(table_opts);
+        }
+        let hook = install_merge_operator::<V>(&mut self.opts, &self.name, self.reducer);
+        let kv = if self.column_families.is_empty() {
+            KeyValueDB::open_with_opts(path, self.opts)?
+        } else {
+            self.opts.create_missing_column_families(true);
+            KeyValueDB::open_cf_with_opts(path, self.opts, &self.column_families)?
+        };
+        if !self.dynamic_opts.is_empty() {
+            let opts: Vec<(&str, &str)> = self
+                .dynamic_opts
+                .iter()
+                .map(|(name, value)| (*name, value.as_str()))
+                .collect();
+            kv.db.set_options(&opts)?;
+        }
+        if self.compact_on_open {
+            kv.compact_all();
+        }
+        Ok(MergeableDB { kv, hook })
+    }
+}
+
+impl<KRef, V, VRef, K> Default for MergeableDBBuilder<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: AssociateMergeable + 'static,
+    VRef: TrySerializable,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,186 @@
+//! Introspection of the files backing a database.
+
+use rocksdb::ReadOptions;
+
+use crate::{properties, serialize, Deserializable, KeyValueDB, RustyRocksError, TrySerializable};
+
+/// An SST file currently in use by a database, from `KeyValueDB::live_files`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveFileMeta<K> {
+    /// The file's name, relative to the database directory.
+    pub name: String,
+    pub column_family: String,
+    /// The LSM level the file is in, with 0 holding the newest data.
+    pub level: i32,
+    /// The file's size in bytes.
+    pub size: usize,
+    pub num_entries: u64,
+    pub num_deletions: u64,
+    /// The smallest key in the file, or `None` if it fails to deserialize.
+    pub smallest_key: Option<K>,
+    /// The largest key in the file, or `None` if it fails to deserialize.
+    pub largest_key: Option<K>,
+}
+
+impl<KRef, V, VRef, K> KeyValueDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    /// List the SST files the database is currently made of.
+    ///
+    /// Data still in memtables is in no file; `flush` first to include it.
+    pub fn live_files(&self) -> Result<Vec<LiveFileMeta<K>>, RustyRocksError>
+    where
+        K: Deserializable,
+    {
+        let decode = |key: Option<Vec<u8>>| key.and_then(|k| K::deserialize(&k).ok());
+        Ok(self
+            .db
+            .live_files()?
+            .into_iter()
+            .map(|file| LiveFileMeta {
+                name: file.name,
+                column_family: file.column_family_name,
+                level: file.level,
+                size: file.size,
+                num_entries: file.num_entries,
+                num_deletions: file.num_deletions,
+                smallest_key: decode(file.start_key),
+                largest_key: decode(file.end_key),
+            })
+            .collect())
+    }
+
+    /// An estimate of the bytes on disk taken by the keys from `start` up to
+    /// but not including `end`.
+    ///
+    /// rocksdb's size approximation is not exposed to Rust, so this adds up
+    /// the SST files whose keys overlap the range. A file only partly in the
+    /// range counts in full, so this overestimates small ranges; and data
+    /// still in memtables counts for nothing until it is flushed. Key ranges
+    /// are compared bytewise, so this assumes the default comparator.
+    pub fn size_in_range(&self, start: KRef, end: KRef) -> Result<u64, RustyRocksError> {
+        let start = serialize(start)?;
+        let end = serialize(end)?;
+        let (start, end) = (start.as_ref(), end.as_ref());
+        Ok(self
+            .db
+            .live_files()?
+            .into_iter()
+            .filter(|file| file.column_family_name == "default")
+            .filter(|file| match (&file.start_key, &file.end_key) {
+                (Some(first), Some(last)) => first.as_slice() < end && last.as_slice() >= start,
+                _ => false,
+            })
+            .map(|file| file.size as u64)
+            .sum())
+    }
+
+    /// The number of keys from `start` up to but not including `end`:
+    /// counted exactly by iterating over them if `exact` is set, and
+    /// otherwise estimated cheaply from SST file and memtable metadata.
+    ///
+    /// The estimate adds up the entries of the SST files overlapping the
+    /// range, taking the share of a file that only partly overlaps in
+    /// proportion to where the range's ends fall between its smallest and
+    /// largest keys. Memtables record no key range, so the entries not yet
+    /// flushed are shared out the same way between the database's first and
+    /// last keys, found with two seeks. So it is good for ranges of evenly
+    /// spread keys, and rough otherwise: overwritten and deleted keys still
+    /// count until compaction drops them. Key ranges are compared bytewise,
+    /// so it assumes the default comparator.
+    pub fn range_count(&self, start: KRef, end: KRef, exact: bool) -> Result<u64, RustyRocksError> {
+        let start = serialize(start)?;
+        let end = serialize(end)?;
+        let (start, end) = (start.as_ref(), end.as_ref());
+        if exact {
+            let mut opts = ReadOptions::default();
+            opts.set_iterate_upper_bound(end);
+            // A prefix extractor would otherwise stop the count at the end of
+            // `start`'s prefix.
+            opts.set_total_order_seek(true);
+            let mut iter = self.db.raw_iterator_opt(opts);
+            iter.seek(start);
+            let mut n = 0;
+            while iter.valid() {
+                n += 1;
+                iter.next();
+            }
+            iter.status()?;
+            return Ok(n);
+        }
+        let estimate: f64 = self
+            .db
+            .live_files()?
+            .into_iter()
+            .filter(|file| file.column_family_name == "default")
+            .filter_map(|file| {
+                let (first, last) = (file.start_key?, file.end_key?);
+                if first.as_slice() >= end || last.as_slice() < start {
+                    return None;
+                }
+                let live = file.num_entries.saturating_sub(file.num_deletions) as f64;
+                Some(live * overlap(&first, &last, start, end))
+            })
+            .sum();
+        Ok((estimate + self.memtable_range_count(start, end)?).round() as u64)
+    }
+
+    // The entries in memtables, shared out evenly between the database's
+    // first and last keys, that fall from `start` up to `end`.
+    fn memtable_range_count(&self, start: &[u8], end: &[u8]) -> Result<f64, RustyRocksError> {
+        let mut entries = 0;
+        for name in [
+            properties::NUM_ENTRIES_ACTIVE_MEM_TABLE,
+            properties::NUM_ENTRIES_IMM_MEM_TABLES,
+        ] {
+            entries += self.db.property_int_value(name)?.unwrap_or(0);
+        }
+        if entries == 0 {
+            return Ok(0.0);
+        }
+        let mut opts = ReadOptions::default();
+        opts.set_total_order_seek(true);
+        let mut iter = self.db.raw_iterator_opt(opts);
+        iter.seek_to_first();
+        let first = iter.key().map(<[u8]>::to_vec);
+        iter.seek_to_last();
+        let last = iter.key().map(<[u8]>::to_vec);
+        iter.status()?;
+        match (first, last) {
+            (Some(first), Some(last)) if first.as_slice() < end && last.as_slice() >= start => {
+                Ok(entries as f64 * overlap(&first, &last, start, end))
+            }
+            _ => Ok(0.0),
+        }
+    }
+}
+
+// The share of the keys from `first` to `last` inclusive that lie from `start`
+// up to `end`, treating keys as numbers spread evenly between the two.
+fn overlap(first: &[u8], last: &[u8], start: &[u8], end: &[u8]) -> f64 {
+    // Past the prefix every key in the file shares, the next eight bytes
+    // place a key between `first` and `last`.
+    let common = first.iter().zip(last).take_while(|(a, b)| a == b).count();
+    let position = |key: &[u8]| {
+        let key = if key < first {
+            first
+        } else if key > last {
+            last
+        } else {
+            key
+        };
+        let mut bytes = [0; 8];
+        for (b, k) in bytes.iter_mut().zip(key.iter().skip(common)) {
+            *b = *k;
+        }
+        u64::from_be_bytes(bytes) as f64
+    };
+    let (lo, hi) = (position(first), position(last));
+    if hi <= lo {
+        return 1.0;
+    }
+    ((position(end) - position(start)) / (hi - lo)).clamp(0.0, 1.0)
+}
@@ -0,0 +1,78 @@
+//! Running minimums and maximums kept with a merge operator.
+
+use std::path::Path;
+
+use crate::{
+    AssociateMergeable, Deserializable, KeyRef, MergePolicy, MergeableDB, RustyRocksError,
+    Serializable, TrySerializable, TupleError,
+};
+
+// The smallest and largest values observed, stored as an `(i64, i64)` tuple
+// and merged by taking the smaller minimum and the larger maximum.
+struct MinMax(i64, i64);
+
+impl Serializable for MinMax {
+    type Bytes = Vec<u8>;
+
+    fn serialize(self) -> Vec<u8> {
+        (self.0, self.1).serialize()
+    }
+}
+
+impl Deserializable for MinMax {
+    type Error = TupleError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, TupleError> {
+        <(i64, i64)>::deserialize(bytes).map(|(min, max)| MinMax(min, max))
+    }
+}
+
+impl AssociateMergeable for MinMax {
+    fn merge(&mut self, other: Self) {
+        self.0 = self.0.min(other.0);
+        self.1 = self.1.max(other.1);
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.serialize()
+    }
+
+    fn handle_deser_error(_key: &[u8], _bytes: &[u8], _err: TupleError) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
+
+/// A database tracking the smallest and largest `i64` observed under each
+/// key, where observing a value is a merge rather than a read followed by a
+/// write.
+pub struct MinMaxDB<KRef, K = <KRef as KeyRef>::Owned> {
+    db: MergeableDB<KRef, MinMax, MinMax, K>,
+}
+
+impl<KRef, K> MinMaxDB<KRef, K>
+where
+    KRef: TrySerializable,
+{
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        Ok(MinMaxDB {
+            db: MergeableDB::with_name(path, "rustyrocks min max")?,
+        })
+    }
+
+    /// Record `value` as observed under `k`.
+    pub fn observe(&self, k: KRef, value: i64) -> Result<(), RustyRocksError> {
+        self.db.merge(k, MinMax(value, value))
+    }
+
+    /// The smallest and largest values observed under `k`, or `None` if
+    /// none have been.
+    pub fn get_min_max(&self, k: KRef) -> Result<Option<(i64, i64)>, RustyRocksError> {
+        Ok(self.db.get(k)?.map(|m| (m.0, m.1)))
+    }
+
+    /// Forget the values observed under `k`.
+    pub fn reset(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.db.delete(k)
+    }
+}
@@ -0,0 +1,199 @@
+//! Values updated by a log of operations, rather than by merging whole values.
+
+use std::fmt;
+use std::iter::FromIterator;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{AssociateMergeable, Deserializable, MergePolicy, TrySerializable};
+
+/// A value that `Operation`s can be applied to.
+///
+/// Operations merged into a key that has no value yet are applied to
+/// `Default::default()`.
+pub trait Operate: Default {
+    type Item;
+
+    fn add(&mut self, item: Self::Item);
+
+    fn remove(&mut self, item: Self::Item);
+}
+
+/// A single update to an `Operate` value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, T::Item: Serialize",
+    deserialize = "T: Deserialize<'de>, T::Item: Deserialize<'de>"
+))]
+pub enum Operation<T: Operate> {
+    Add(T::Item),
+    Remove(T::Item),
+    /// Discard the value and everything applied to it so far.
+    Replace(T),
+}
+
+/// The value type of a `MergeableDB` whose operands are `Operation`s.
+///
+/// Each operand is a list of operations, usually just one. Merging applies
+/// them in order. A full merge always starts from the default or an existing
+/// value, so it collapses everything into a single `Replace` and a key's
+/// stored value stays the size of its value. Partial merges, which do not
+/// know what they will be applied to, keep operations pending until `value`
+/// or a later full merge applies them. Operations are stored with bincode.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, T::Item: Serialize",
+    deserialize = "T: Deserialize<'de>, T::Item: Deserialize<'de>"
+))]
+pub struct Operations<T: Operate>(Vec<Operation<T>>);
+
+impl<T: Operate> Operations<T> {
+    pub fn add(item: T::Item) -> Self {
+        Operations(vec![Operation::Add(item)])
+    }
+
+    pub fn remove(item: T::Item) -> Self {
+        Operations(vec![Operation::Remove(item)])
+    }
+
+    pub fn replace(value: T) -> Self {
+        Operations(vec![Operation::Replace(value)])
+    }
+
+    /// Apply `op` after everything already here.
+    pub fn push(&mut self, op: Operation<T>) {
+        match (self.0.first_mut(), op) {
+            (_, Operation::Replace(value)) => {
+                self.0.clear();
+                self.0.push(Operation::Replace(value));
+            }
+            // Once there is a base value, later operations fold straight into it.
+            (Some(Operation::Replace(base)), Operation::Add(item)) => base.add(item),
+            (Some(Operation::Replace(base)), Operation::Remove(item)) => base.remove(item),
+            (_, op) => self.0.push(op),
+        }
+    }
+
+    // Apply everything here to the default, leaving a single `Replace`.
+    fn collapse(&mut self) {
+        if !matches!(self.0.as_slice(), [Operation::Replace(_)]) {
+            let base = Operations(std::mem::take(&mut self.0)).value();
+            self.0.push(Operation::Replace(base));
+        }
+    }
+
+    /// The value these operations produce.
+    pub fn value(self) -> T {
+        let mut value = T::default();
+        for op in self.0 {
+            match op {
+                Operation::Add(item) => value.add(item),
+                Operation::Remove(item) => value.remove(item),
+                Operation::Replace(v) => value = v,
+            }
+        }
+        value
+    }
+}
+
+impl<T: Operate> fmt::Debug for Operations<T>
+where
+    Operation<T>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Operations").field(&self.0).finish()
+    }
+}
+
+impl<T: Operate> Clone for Operations<T>
+where
+    Operation<T>: Clone,
+{
+    fn clone(&self) -> Self {
+        Operations(self.0.clone())
+    }
+}
+
+impl<T: Operate> PartialEq for Operations<T>
+where
+    Operation<T>: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Operate> Eq for Operations<T> where Operation<T>: Eq {}
+
+impl<T: Operate> From<Operation<T>> for Operations<T> {
+    fn from(op: Operation<T>) -> Self {
+        Operations(vec![op])
+    }
+}
+
+impl<T: Operate> FromIterator<Operation<T>> for Operations<T> {
+    fn from_iter<I: IntoIterator<Item = Operation<T>>>(iter: I) -> Self {
+        let mut ops = Operations(Vec::new());
+        for op in iter {
+            ops.push(op);
+        }
+        ops
+    }
+}
+
+impl<T: Operate + Serialize> TrySerializable for &Operations<T>
+where
+    T::Item: Serialize,
+{
+    type Bytes = Vec<u8>;
+    type Error = bincode::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+}
+
+impl<T: Operate + DeserializeOwned> Deserializable for Operations<T>
+where
+    T::Item: DeserializeOwned,
+{
+    type Error = bincode::Error;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
+
+impl<T> AssociateMergeable for Operations<T>
+where
+    T: Operate + Serialize + DeserializeOwned,
+    T::Item: Serialize + DeserializeOwned,
+{
+    fn merge(&mut self, other: Self) {
+        self.collapse();
+        self.partial_merge(other);
+    }
+
+    fn partial_merge(&mut self, other: Self) {
+        for op in other.0 {
+            self.push(op);
+        }
+    }
+
+    fn settle(mut self) -> Self {
+        self.collapse();
+        self
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        (&self)
+            .try_serialize()
+            .expect("bincode can encode any serde value")
+    }
+
+    fn handle_deser_error(_key: &[u8], _bytes: &[u8], _err: bincode::Error) -> MergePolicy<Self> {
+        // Skipping an operation would silently apply the rest out of context.
+        MergePolicy::Abort
+    }
+}
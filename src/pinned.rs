@@ -0,0 +1,55 @@
+//! Values read without copying them out of rocksdb.
+
+use std::fmt;
+use std::ops::Deref;
+
+use rocksdb::DBPinnableSlice;
+
+use crate::{deserialize, BorrowDeserialize, Deserializable, RustyRocksError};
+
+/// The stored bytes of a value, pinned in rocksdb's cache for as long as this
+/// guard lives.
+///
+/// Returned by `KeyValueDB::get_raw`. It dereferences to the bytes, and can
+/// be deserialized as any type rather than only as the database's `V`.
+pub struct PinnedValue<'a> {
+    slice: DBPinnableSlice<'a>,
+}
+
+impl<'a> PinnedValue<'a> {
+    pub(crate) fn new(slice: DBPinnableSlice<'a>) -> Self {
+        PinnedValue { slice }
+    }
+
+    pub fn deserialize<T: Deserializable>(&self) -> Result<T, RustyRocksError> {
+        deserialize(self)
+    }
+
+    /// Deserialize a view borrowing from the pinned bytes.
+    pub fn deserialize_borrowed<'b, T>(&'b self) -> Result<T, RustyRocksError>
+    where
+        T: BorrowDeserialize<'b>,
+    {
+        T::deserialize_borrowed(self).map_err(RustyRocksError::deserialize)
+    }
+}
+
+impl Deref for PinnedValue<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.slice
+    }
+}
+
+impl AsRef<[u8]> for PinnedValue<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl fmt::Debug for PinnedValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PinnedValue").field(&&**self).finish()
+    }
+}
@@ -0,0 +1,299 @@
+//! Fixed-width encodings for primitive types, byte arrays and tuples of them.
+//!
+//! Everything here is encoded so that the lexicographic order of the bytes,
+//! which is how rocksdb sorts keys, matches the natural order of the values.
+
+use std::convert::TryInto;
+
+use crate::{Deserializable, KeyRef, LengthError, PrimitiveError, Serializable, TupleError};
+
+/// A type whose serialized form is always `WIDTH` bytes long.
+///
+/// Tuples of fixed-width types are serialized by concatenating their
+/// components, which only splits back apart unambiguously because every
+/// component has a known width.
+pub trait FixedWidth {
+    const WIDTH: usize;
+}
+
+fn fixed<const N: usize>(bytes: &[u8]) -> Result<[u8; N], LengthError> {
+    bytes.try_into().map_err(|_| LengthError {
+        expected: N,
+        found: bytes.len(),
+    })
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {$(
+        impl Serializable for $t {
+            type Bytes = [u8; std::mem::size_of::<$t>()];
+
+            fn serialize(self) -> Self::Bytes {
+                self.to_be_bytes()
+            }
+        }
+
+        impl FixedWidth for $t {
+            const WIDTH: usize = std::mem::size_of::<$t>();
+        }
+
+        impl KeyRef for $t {
+            type Owned = $t;
+        }
+
+        impl Deserializable for $t {
+            type Error = LengthError;
+
+            fn deserialize(bytes: &[u8]) -> Result<Self, LengthError> {
+                Ok(<$t>::from_be_bytes(fixed(bytes)?))
+            }
+        }
+    )*};
+}
+
+// Flipping the sign bit moves negative numbers below positive ones, while
+// two's complement already orders values of the same sign correctly.
+macro_rules! impl_signed {
+    ($($t:ty),*) => {$(
+        impl Serializable for $t {
+            type Bytes = [u8; std::mem::size_of::<$t>()];
+
+            fn serialize(self) -> Self::Bytes {
+                (self ^ <$t>::MIN).to_be_bytes()
+            }
+        }
+
+        impl FixedWidth for $t {
+            const WIDTH: usize = std::mem::size_of::<$t>();
+        }
+
+        impl KeyRef for $t {
+            type Owned = $t;
+        }
+
+        impl Deserializable for $t {
+            type Error = LengthError;
+
+            fn deserialize(bytes: &[u8]) -> Result<Self, LengthError> {
+                Ok(<$t>::from_be_bytes(fixed(bytes)?) ^ <$t>::MIN)
+            }
+        }
+    )*};
+}
+
+impl_unsigned!(u32, u64);
+impl_signed!(i32, i64);
+
+// IEEE-754 bits sort like sign-magnitude integers. Flipping the sign bit of
+// positive numbers moves them above the negatives, and flipping every bit of
+// negative numbers reverses their order. The result is the IEEE total order:
+// -NaN < -inf < ... < -0.0 < 0.0 < ... < inf < NaN.
+macro_rules! impl_float {
+    ($($t:ty, $bits:ty);*) => {$(
+        impl Serializable for $t {
+            type Bytes = [u8; std::mem::size_of::<$t>()];
+
+            fn serialize(self) -> Self::Bytes {
+                let bits = self.to_bits();
+                let sign = <$bits>::MAX ^ (<$bits>::MAX >> 1);
+                let bits = if bits & sign != 0 { !bits } else { bits ^ sign };
+                bits.to_be_bytes()
+            }
+        }
+
+        impl FixedWidth for $t {
+            const WIDTH: usize = std::mem::size_of::<$t>();
+        }
+
+        impl KeyRef for $t {
+            type Owned = $t;
+        }
+
+        impl Deserializable for $t {
+            type Error = LengthError;
+
+            fn deserialize(bytes: &[u8]) -> Result<Self, LengthError> {
+                let bits = <$bits>::from_be_bytes(fixed(bytes)?);
+                let sign = <$bits>::MAX ^ (<$bits>::MAX >> 1);
+                let bits = if bits & sign != 0 { bits ^ sign } else { !bits };
+                Ok(<$t>::from_bits(bits))
+            }
+        }
+    )*};
+}
+
+impl_float!(f32, u32; f64, u64);
+
+// `false` sorts before `true`.
+impl Serializable for bool {
+    type Bytes = [u8; 1];
+
+    fn serialize(self) -> Self::Bytes {
+        [self as u8]
+    }
+}
+
+impl FixedWidth for bool {
+    const WIDTH: usize = 1;
+}
+
+impl KeyRef for bool {
+    type Owned = bool;
+}
+
+impl Deserializable for bool {
+    type Error = PrimitiveError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, PrimitiveError> {
+        match fixed(bytes)? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(PrimitiveError::Invalid {
+                type_name: "bool",
+                bytes: bytes.to_vec(),
+            }),
+        }
+    }
+}
+
+// Chars are stored as their scalar values, so they sort by code point.
+impl Serializable for char {
+    type Bytes = [u8; 4];
+
+    fn serialize(self) -> Self::Bytes {
+        u32::from(self).to_be_bytes()
+    }
+}
+
+impl FixedWidth for char {
+    const WIDTH: usize = 4;
+}
+
+impl KeyRef for char {
+    type Owned = char;
+}
+
+impl Deserializable for char {
+    type Error = PrimitiveError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, PrimitiveError> {
+        let scalar = u32::from_be_bytes(fixed(bytes)?);
+        char::from_u32(scalar).ok_or_else(|| PrimitiveError::Invalid {
+            type_name: "char",
+            bytes: bytes.to_vec(),
+        })
+    }
+}
+
+impl<const N: usize> FixedWidth for [u8; N] {
+    const WIDTH: usize = N;
+}
+
+impl<const N: usize> KeyRef for [u8; N] {
+    type Owned = [u8; N];
+}
+
+impl<const N: usize> Serializable for [u8; N] {
+    type Bytes = [u8; N];
+
+    fn serialize(self) -> Self::Bytes {
+        self
+    }
+}
+
+impl<const N: usize> Deserializable for [u8; N] {
+    type Error = LengthError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, LengthError> {
+        fixed(bytes)
+    }
+}
+
+// A UUID's bytes in their standard big-endian order, which sorts the same as
+// the UUID's hyphenated text.
+#[cfg(feature = "uuid")]
+impl Serializable for uuid::Uuid {
+    type Bytes = [u8; 16];
+
+    fn serialize(self) -> Self::Bytes {
+        self.into_bytes()
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl FixedWidth for uuid::Uuid {
+    const WIDTH: usize = 16;
+}
+
+#[cfg(feature = "uuid")]
+impl KeyRef for uuid::Uuid {
+    type Owned = uuid::Uuid;
+}
+
+#[cfg(feature = "uuid")]
+impl Deserializable for uuid::Uuid {
+    type Error = LengthError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, LengthError> {
+        Ok(uuid::Uuid::from_bytes(fixed(bytes)?))
+    }
+}
+
+// Concatenating components preserves order: the first component decides,
+// and only ties fall through to the later ones.
+macro_rules! impl_tuple {
+    ($($name:ident $idx:tt),+) => {
+        impl<$($name: FixedWidth),+> FixedWidth for ($($name,)+) {
+            const WIDTH: usize = 0 $(+ $name::WIDTH)+;
+        }
+
+        impl<$($name: FixedWidth + Deserializable),+> KeyRef for ($($name,)+) {
+            type Owned = Self;
+        }
+
+        impl<$($name: FixedWidth + Serializable),+> Serializable for ($($name,)+) {
+            type Bytes = Vec<u8>;
+
+            fn serialize(self) -> Self::Bytes {
+                let mut bytes = Vec::with_capacity(<Self as FixedWidth>::WIDTH);
+                self.serialize_into(&mut bytes);
+                bytes
+            }
+
+            fn serialize_into(self, buf: &mut Vec<u8>) {
+                buf.reserve(<Self as FixedWidth>::WIDTH);
+                $(self.$idx.serialize_into(buf);)+
+            }
+        }
+
+        impl<$($name: FixedWidth + Deserializable),+> Deserializable for ($($name,)+) {
+            type Error = TupleError;
+
+            // The offset past the last component is never read.
+            #[allow(unused_assignments)]
+            fn deserialize(bytes: &[u8]) -> Result<Self, TupleError> {
+                let expected = <Self as FixedWidth>::WIDTH;
+                if bytes.len() != expected {
+                    return Err(LengthError {
+                        expected,
+                        found: bytes.len(),
+                    }
+                    .into());
+                }
+                let mut offset = 0;
+                Ok(($({
+                    let component = &bytes[offset..offset + $name::WIDTH];
+                    offset += $name::WIDTH;
+                    $name::deserialize(component).map_err(|e| TupleError::Component {
+                        index: $idx,
+                        source: Box::new(e),
+                    })?
+                },)+))
+            }
+        }
+    };
+}
+
+impl_tuple!(A 0, B 1);
+impl_tuple!(A 0, B 1, C 2);
+impl_tuple!(A 0, B 1, C 2, D 3);
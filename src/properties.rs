@@ -0,0 +1,29 @@
+//! Names of commonly used rocksdb properties, for passing to
+//! `KeyValueDB::property_value` and `KeyValueDB::property_int_value`.
+
+/// Human-readable statistics about the database and its column families.
+pub const STATS: &str = "rocksdb.stats";
+
+/// A summary of the current SST files.
+pub const SSTABLES: &str = "rocksdb.sstables";
+
+/// The approximate size in bytes of all memtables.
+pub const CUR_SIZE_ALL_MEM_TABLES: &str = "rocksdb.cur-size-all-mem-tables";
+
+/// The number of entries, including deletions, in the memtable being written.
+pub const NUM_ENTRIES_ACTIVE_MEM_TABLE: &str = "rocksdb.num-entries-active-mem-table";
+
+/// The number of entries, including deletions, in memtables awaiting a flush.
+pub const NUM_ENTRIES_IMM_MEM_TABLES: &str = "rocksdb.num-entries-imm-mem-tables";
+
+/// The estimated number of keys; see `KeyValueDB::approximate_len`.
+pub const ESTIMATE_NUM_KEYS: &str = "rocksdb.estimate-num-keys";
+
+/// The estimated size in bytes of live data.
+pub const ESTIMATE_LIVE_DATA_SIZE: &str = "rocksdb.estimate-live-data-size";
+
+/// The total size in bytes of all SST files, including obsolete ones.
+pub const TOTAL_SST_FILES_SIZE: &str = "rocksdb.total-sst-files-size";
+
+/// The number of compactions currently running.
+pub const NUM_RUNNING_COMPACTIONS: &str = "rocksdb.num-running-compactions";
@@ -0,0 +1,58 @@
+//! Values merged by keeping the last one written.
+
+use std::ops::Deref;
+
+use crate::{AssociateMergeable, Deserializable, MergePolicy};
+
+/// A `V` whose merge keeps the latest value written, like a `put`, but with
+/// merge's batching of writes.
+///
+/// It is stored exactly as `V` is, so the `VRef` of a `MergeableDB` holding
+/// it can be `V`'s own, e.g. `MergeableDB<&str, ReplaceMerge<String>, &str>`.
+/// A value that fails to deserialize is skipped, leaving the latest one that
+/// did not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplaceMerge<V> {
+    value: V,
+    // The stored form of `value`, handed back unchanged after a merge.
+    bytes: Vec<u8>,
+}
+
+impl<V> ReplaceMerge<V> {
+    pub fn into_inner(self) -> V {
+        self.value
+    }
+}
+
+impl<V> Deref for ReplaceMerge<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.value
+    }
+}
+
+impl<V: Deserializable> Deserializable for ReplaceMerge<V> {
+    type Error = V::Error;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, V::Error> {
+        Ok(ReplaceMerge {
+            value: V::deserialize(bytes)?,
+            bytes: bytes.to_vec(),
+        })
+    }
+}
+
+impl<V: Deserializable> AssociateMergeable for ReplaceMerge<V> {
+    fn merge(&mut self, other: Self) {
+        *self = other;
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn handle_deser_error(_key: &[u8], _bytes: &[u8], _err: V::Error) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
@@ -0,0 +1,80 @@
+//! A database of serde types, without the separate reference type parameters.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{BincodeCodec, Codec, DBIter, Encoded, KeyValueDB, RustyRocksError};
+
+// Keys are encoded before they reach the inner database, and decoded by it.
+type Inner<K, V, C> = KeyValueDB<Vec<u8>, Encoded<V, C>, Vec<u8>, Encoded<K, C>>;
+
+/// A database storing any serde keys and values with the codec `C`.
+///
+/// Keys and values are written by reference and read back owned, so there is
+/// no `KRef` or `VRef` to spell out.
+///
+/// ```no_run
+/// use rustyrocks::SimpleDB;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let db: SimpleDB<String, User> = SimpleDB::open_default("users.db").unwrap();
+/// let user = User { name: "Ada".into(), age: 36 };
+/// db.put(&"ada".to_string(), &user).unwrap();
+/// let user = db.get(&"ada".to_string()).unwrap().unwrap();
+/// println!("{} is {}", user.name, user.age);
+/// ```
+///
+/// The default codec is bincode, whose encoding does not sort keys the way
+/// `K` does, so iteration visits keys in the order of their encoded bytes.
+pub struct SimpleDB<K, V, C = BincodeCodec> {
+    kv: Inner<K, V, C>,
+}
+
+impl<K, V, C> SimpleDB<K, V, C>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    C: Codec<K> + Codec<V>,
+{
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn open_default<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        Ok(SimpleDB {
+            kv: KeyValueDB::open_default(path)?,
+        })
+    }
+
+    pub fn get(&self, k: &K) -> Result<Option<V>, RustyRocksError> {
+        Ok(self.kv.get(encode::<C, K>(k)?)?.map(Encoded::into_inner))
+    }
+
+    pub fn put(&self, k: &K, v: &V) -> Result<(), RustyRocksError> {
+        self.kv.put(encode::<C, K>(k)?, encode::<C, V>(v)?)
+    }
+
+    pub fn delete(&self, k: &K) -> Result<(), RustyRocksError> {
+        self.kv.delete(encode::<C, K>(k)?)
+    }
+
+    /// Iterate over the key-value pairs, in the order of their encoded keys.
+    pub fn db_iter(&self) -> impl Iterator<Item = Result<(K, V), RustyRocksError>> + '_ {
+        let iter: DBIter<'_, Encoded<K, C>, Encoded<V, C>> = self.kv.db_iter();
+        iter.map(|kv| kv.map(|(k, v)| (k.into_inner(), v.into_inner())))
+    }
+
+    /// The underlying database, for everything else.
+    pub fn inner(&self) -> &Inner<K, V, C> {
+        &self.kv
+    }
+}
+
+fn encode<C: Codec<T>, T>(value: &T) -> Result<Vec<u8>, RustyRocksError> {
+    C::encode(value).map_err(RustyRocksError::serialize)
+}
@@ -0,0 +1,42 @@
+use std::marker::PhantomData;
+
+use crate::{
+    deserialize, serialize, DBIter, Deserializable, KeyRef, RustyRocksError, TrySerializable,
+};
+
+/// A point-in-time view of a `KeyValueDB`.
+///
+/// Reads through a snapshot all observe the database as it was when the
+/// snapshot was taken, regardless of later writes.
+pub struct Snapshot<'a, KRef, V, K = <KRef as KeyRef>::Owned> {
+    snapshot: rocksdb::Snapshot<'a>,
+    phantom: PhantomData<(KRef, V, K)>,
+}
+
+impl<'a, KRef, V, K> Snapshot<'a, KRef, V, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+{
+    pub(crate) fn new(snapshot: rocksdb::Snapshot<'a>) -> Self {
+        Snapshot {
+            snapshot,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn get(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        match self.snapshot.get_pinned(serialize(k)?)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Iterate over all key-value pairs in the snapshot, in key order.
+    pub fn db_iter(&self) -> DBIter<'_, K, V>
+    where
+        K: Deserializable,
+    {
+        DBIter::from_snapshot(&self.snapshot)
+    }
+}
@@ -0,0 +1,44 @@
+use std::marker::PhantomData;
+use std::path::Path;
+
+use rocksdb::{Options, SstFileWriter};
+
+use crate::{serialize, RustyRocksError, TrySerializable};
+
+/// Writes typed key-value pairs to an SST file, for loading into a database
+/// with `KeyValueDB::ingest_sst_files`.
+///
+/// Keys must be written in strictly increasing order of their serialized
+/// bytes; writing a key out of order fails.
+pub struct SstWriter<'a, KRef, VRef> {
+    writer: SstFileWriter<'a>,
+    phantom: PhantomData<(KRef, VRef)>,
+}
+
+impl<'a, KRef, VRef> SstWriter<'a, KRef, VRef>
+where
+    KRef: TrySerializable,
+    VRef: TrySerializable,
+{
+    /// Create the SST file at `path`. `opts` should match those the database
+    /// it will be ingested into was opened with.
+    pub fn create<P: AsRef<Path>>(opts: &'a Options, path: P) -> Result<Self, RustyRocksError> {
+        let writer = SstFileWriter::create(opts);
+        writer.open(path)?;
+        Ok(SstWriter {
+            writer,
+            phantom: PhantomData,
+        })
+    }
+
+    pub fn put(&mut self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.writer.put(serialize(k)?, serialize(v)?)?;
+        Ok(())
+    }
+
+    /// Finish writing the file, which must hold at least one entry.
+    pub fn finish(mut self) -> Result<(), RustyRocksError> {
+        self.writer.finish()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+//! Sorted lists of strings, merged by union.
+
+use std::iter::FromIterator;
+
+use itertools::Itertools;
+use rmp::decode::read_str_from_slice;
+use rmp::encode::write_str;
+
+use crate::{AssociateMergeable, Deserializable, MergePolicy, Serializable, StringListError};
+
+/// A sorted list of distinct strings, merged by taking the union of the
+/// lists.
+///
+/// The strings are stored one after another as msgpack strings, in order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SortedStringList(Vec<String>);
+
+impl SortedStringList {
+    pub fn new() -> Self {
+        SortedStringList::default()
+    }
+
+    /// A list of just `s`, to merge into a key.
+    pub fn single<S: Into<String>>(s: S) -> Self {
+        SortedStringList(vec![s.into()])
+    }
+
+    /// Add `s` in its place in the order, unless it is already present.
+    pub fn push<S: Into<String>>(&mut self, s: S) {
+        let s = s.into();
+        if let Err(i) = self.0.binary_search(&s) {
+            self.0.insert(i, s);
+        }
+    }
+
+    pub fn contains(&self, s: &str) -> bool {
+        self.0.binary_search_by(|t| t.as_str().cmp(s)).is_ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The strings, in order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    pub fn into_vec(self) -> Vec<String> {
+        self.0
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for SortedStringList {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut strings: Vec<String> = iter.into_iter().map(Into::into).collect();
+        strings.sort_unstable();
+        strings.dedup();
+        SortedStringList(strings)
+    }
+}
+
+impl Serializable for &SortedStringList {
+    type Bytes = Vec<u8>;
+
+    fn serialize(self) -> Vec<u8> {
+        let len = self.0.iter().map(|s| s.len() + 5).sum();
+        let mut bytes = Vec::with_capacity(len);
+        for s in &self.0 {
+            write_str(&mut bytes, s).expect("writing to a Vec cannot fail");
+        }
+        bytes
+    }
+}
+
+impl Deserializable for SortedStringList {
+    type Error = StringListError;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, StringListError> {
+        let mut strings: Vec<String> = Vec::new();
+        let mut unparsed = bytes;
+        while !unparsed.is_empty() {
+            let (s, tail) = read_str_from_slice(unparsed).map_err(|_| StringListError {
+                bytes: bytes.to_vec(),
+            })?;
+            // Anything out of order was not written by this type.
+            if strings.last().is_some_and(|last| last.as_str() >= s) {
+                return Err(StringListError {
+                    bytes: bytes.to_vec(),
+                });
+            }
+            strings.push(s.to_owned());
+            unparsed = tail;
+        }
+        Ok(SortedStringList(strings))
+    }
+}
+
+impl AssociateMergeable for SortedStringList {
+    fn merge(&mut self, other: Self) {
+        let ours = std::mem::take(&mut self.0);
+        self.0 = ours.into_iter().merge(other.0).dedup().collect();
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        (&self).serialize()
+    }
+
+    fn handle_deser_error(_key: &[u8], _bytes: &[u8], _err: StringListError) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
@@ -0,0 +1,47 @@
+//! Databases in temporary directories, removed when they are dropped.
+
+use std::ops::Deref;
+
+use tempfile::TempDir;
+
+use crate::{Deserializable, KeyRef, KeyValueDB, RustyRocksError, TrySerializable};
+
+/// A `KeyValueDB` in a temporary directory of its own, which is removed
+/// along with the database when this is dropped.
+///
+/// Created by `KeyValueDB::temporary`. It dereferences to the database.
+/// Handles that share the database, from `to_async` or `owned_iter`, must be
+/// dropped first, or the directory is removed from under them.
+pub struct TemporaryDB<KRef, V, VRef, K = <KRef as KeyRef>::Owned> {
+    // Declared before the directory so that the database is closed before the
+    // directory is removed.
+    kv: KeyValueDB<KRef, V, VRef, K>,
+    _dir: TempDir,
+}
+
+impl<KRef, V, VRef, K> KeyValueDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    /// Create a new, empty database in a temporary directory.
+    ///
+    /// This suits tests and examples, which would otherwise need to find an
+    /// unused path and clean it up afterwards.
+    pub fn temporary() -> Result<TemporaryDB<KRef, V, VRef, K>, RustyRocksError> {
+        let dir = tempfile::Builder::new().prefix("rustyrocks").tempdir()?;
+        Ok(TemporaryDB {
+            kv: KeyValueDB::open_default(dir.path())?,
+            _dir: dir,
+        })
+    }
+}
+
+impl<KRef, V, VRef, K> Deref for TemporaryDB<KRef, V, VRef, K> {
+    type Target = KeyValueDB<KRef, V, VRef, K>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.kv
+    }
+}
@@ -0,0 +1,85 @@
+//! Helpers for testing code built on this crate.
+
+use std::collections::BTreeMap;
+use std::thread;
+
+use crate::KeyValueDB;
+
+// A xorshift generator, so that runs are repeatable without a dependency on
+// a random number crate.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+// Keys each thread writes, out of a range of its own.
+const KEYS_PER_THREAD: u64 = 64;
+
+/// Run `ops` random puts, gets, deletes and batched puts against `db` on each
+/// of `threads` threads at once, checking every read and then the final
+/// contents against an in-memory model.
+///
+/// Each thread works on a range of keys of its own, so the model is exact
+/// whatever order the threads run in, while the threads still share the
+/// database and its memtables, write-ahead log and compactions. `db` must
+/// start out empty. The runs are the same every time.
+///
+/// # Panics
+///
+/// If any operation fails, or a read or the final contents disagree with
+/// the model.
+pub fn hammer(db: &KeyValueDB<u64, u64, u64>, threads: usize, ops: usize) {
+    let models: Vec<BTreeMap<u64, u64>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads as u64)
+            .map(|t| scope.spawn(move || hammer_thread(db, t, ops)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("hammer thread panicked"))
+            .collect()
+    });
+    let expected: Vec<(u64, u64)> = models.into_iter().flatten().collect();
+    let found: Vec<(u64, u64)> = db
+        .db_iter()
+        .collect::<Result<_, _>>()
+        .expect("reading back the database failed");
+    assert_eq!(found, expected, "database does not match the model");
+}
+
+fn hammer_thread(db: &KeyValueDB<u64, u64, u64>, t: u64, ops: usize) -> BTreeMap<u64, u64> {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15 ^ (t + 1).wrapping_mul(0xbf58_476d_1ce4_e5b9));
+    let mut model = BTreeMap::new();
+    let first = t * KEYS_PER_THREAD;
+    for _ in 0..ops {
+        let k = first + rng.next() % KEYS_PER_THREAD;
+        match rng.next() % 8 {
+            0..=2 => {
+                let v = rng.next();
+                db.put(k, v).expect("put failed");
+                model.insert(k, v);
+            }
+            3..=5 => {
+                let found = db.get(k).expect("get failed");
+                assert_eq!(found, model.get(&k).copied(), "get({}) disagrees", k);
+            }
+            6 => {
+                db.delete(k).expect("delete failed");
+                model.remove(&k);
+            }
+            _ => {
+                let v = rng.next();
+                let next = first + (k - first + 1) % KEYS_PER_THREAD;
+                let pairs = [(k, v), (next, v ^ 1)];
+                db.try_extend(pairs.iter().copied()).expect("batch failed");
+                model.extend(pairs.iter().copied());
+            }
+        }
+    }
+    model
+}
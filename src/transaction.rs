@@ -0,0 +1,179 @@
+use std::convert::TryInto;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::time::Duration;
+
+use rocksdb::{
+    ErrorKind, Options, TransactionDB, TransactionDBOptions, TransactionOptions, WriteOptions,
+};
+
+use crate::{deserialize, serialize, Deserializable, KeyRef, RustyRocksError, TrySerializable};
+
+/// A typed wrapper around a rocksdb `TransactionDB`.
+///
+/// Writes made through a `Transaction` lock the keys they touch, so two
+/// transactions writing the same key cannot both commit.
+///
+/// As with `KeyValueDB`, `K` is the owned form of the key type.
+pub struct TransactionalDB<KRef, V, VRef, K = <KRef as KeyRef>::Owned> {
+    db: TransactionDB,
+    lock_timeout: Option<Duration>,
+    phantom: PhantomData<(KRef, V, VRef, K)>,
+}
+
+impl<KRef, V, VRef, K> TransactionalDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn open_default<P: AsRef<Path>>(path: P) -> Result<Self, RustyRocksError> {
+        Ok(Self::from_db(TransactionDB::open_default(path)?))
+    }
+
+    /// Open the database at `path` with the given rocksdb options.
+    pub fn open_with_opts<P: AsRef<Path>>(
+        path: P,
+        opts: Options,
+        txn_db_opts: TransactionDBOptions,
+    ) -> Result<Self, RustyRocksError> {
+        Ok(Self::from_db(TransactionDB::open(
+            &opts,
+            &txn_db_opts,
+            path,
+        )?))
+    }
+
+    fn from_db(db: TransactionDB) -> Self {
+        TransactionalDB {
+            db,
+            lock_timeout: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Read the committed value for `k`, outside of any transaction.
+    pub fn get(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        match self.db.get_pinned(serialize(k)?)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Delete `k` in a transaction of its own, returning the value it had.
+    ///
+    /// `k` is locked from the read until the delete commits, so the value
+    /// returned is exactly the one deleted.
+    pub fn take(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        let txn = self.transaction();
+        let key = serialize(k)?;
+        let value = match txn
+            .txn
+            .get_pinned_for_update(&key, true)
+            .map_err(txn_error)?
+        {
+            Some(bytes) => deserialize(&bytes)?,
+            None => return Ok(None),
+        };
+        txn.txn.delete(&key).map_err(txn_error)?;
+        txn.commit()?;
+        Ok(Some(value))
+    }
+
+    /// Make transactions begun from now on wait at most `timeout` for a lock,
+    /// after which the operation waiting fails with
+    /// `RustyRocksError::TimedOut`.
+    ///
+    /// Without this, rocksdb's default of one second applies.
+    pub fn set_lock_timeout(&mut self, timeout: Duration) {
+        self.lock_timeout = Some(timeout);
+    }
+
+    /// Begin a transaction.
+    ///
+    /// Nothing it writes is visible to other readers until `commit`. Dropping
+    /// the transaction without committing discards its writes.
+    pub fn transaction(&self) -> Transaction<'_, KRef, V, VRef, K> {
+        let mut txn_opts = TransactionOptions::default();
+        if let Some(timeout) = self.lock_timeout {
+            txn_opts.set_lock_timeout(timeout.as_millis().try_into().unwrap_or(i64::MAX));
+        }
+        Transaction {
+            txn: self.db.transaction_opt(&WriteOptions::default(), &txn_opts),
+            phantom: PhantomData,
+        }
+    }
+}
+
+fn txn_error(e: rocksdb::Error) -> RustyRocksError {
+    match e.kind() {
+        ErrorKind::TimedOut => RustyRocksError::TimedOut,
+        _ => e.into(),
+    }
+}
+
+/// An open transaction on a `TransactionalDB`.
+///
+/// Writing a key that another open transaction has written or locked fails
+/// with `RustyRocksError::TimedOut` once the lock wait times out.
+pub struct Transaction<'a, KRef, V, VRef, K = <KRef as KeyRef>::Owned> {
+    txn: rocksdb::Transaction<'a, TransactionDB>,
+    phantom: PhantomData<(KRef, V, VRef, K)>,
+}
+
+impl<'a, KRef, V, VRef, K> Transaction<'a, KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    /// Read the value for `k`, including this transaction's own writes.
+    pub fn get(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        match self.txn.get_pinned(serialize(k)?).map_err(txn_error)? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the value for `k` and lock it until the transaction ends, so that
+    /// no other transaction can write it in the meantime.
+    ///
+    /// An `exclusive` lock also stops other transactions from locking `k`
+    /// themselves, while a shared one only stops them writing it. Use this
+    /// rather than `get` for read-modify-write.
+    pub fn get_for_update(&self, k: KRef, exclusive: bool) -> Result<Option<V>, RustyRocksError> {
+        match self
+            .txn
+            .get_for_update(serialize(k)?, exclusive)
+            .map_err(txn_error)?
+        {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.txn
+            .put(serialize(k)?, serialize(v)?)
+            .map_err(txn_error)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.txn.delete(serialize(k)?).map_err(txn_error)?;
+        Ok(())
+    }
+
+    /// Make the transaction's writes visible and release its locks.
+    pub fn commit(self) -> Result<(), RustyRocksError> {
+        self.txn.commit().map_err(txn_error)?;
+        Ok(())
+    }
+
+    /// Discard the transaction's writes and release its locks.
+    pub fn rollback(self) -> Result<(), RustyRocksError> {
+        self.txn.rollback()?;
+        Ok(())
+    }
+}
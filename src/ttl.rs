@@ -0,0 +1,63 @@
+use std::path::Path;
+use std::time::Duration;
+
+use rocksdb::{Options, DB};
+
+use crate::{DBIter, Deserializable, KeyRef, KeyValueDB, RustyRocksError, TrySerializable};
+
+/// A `KeyValueDB` whose entries expire a fixed time after they are written.
+///
+/// Expiry is enforced by compaction, not by reads: an expired entry can still
+/// be read until a compaction reaches it. Call `compact_all` to drop every
+/// expired entry at once.
+pub struct TtlDB<KRef, V, VRef, K = <KRef as KeyRef>::Owned> {
+    kv: KeyValueDB<KRef, V, VRef, K>,
+}
+
+impl<KRef, V, VRef, K> TtlDB<KRef, V, VRef, K>
+where
+    KRef: TrySerializable,
+    V: Deserializable,
+    VRef: TrySerializable,
+{
+    /// Open the database at `path`, creating it if it does not exist, with
+    /// entries expiring `ttl` after they were written.
+    ///
+    /// rocksdb counts `ttl` in whole seconds and treats zero as no expiry at
+    /// all, so `ttl` is rounded up to whole seconds, and to at least one.
+    /// It is also capped at `i32::MAX` seconds, about 68 years.
+    pub fn new<P: AsRef<Path>>(path: P, ttl: Duration) -> Result<Self, RustyRocksError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let secs = ttl.as_secs() + u64::from(ttl.subsec_nanos() > 0);
+        let ttl = Duration::from_secs(secs.clamp(1, i32::MAX as u64));
+        let db = DB::open_with_ttl(&opts, path, ttl)?;
+        Ok(TtlDB {
+            kv: KeyValueDB::from_db(db, false),
+        })
+    }
+
+    pub fn get(&self, k: KRef) -> Result<Option<V>, RustyRocksError> {
+        self.kv.get(k)
+    }
+
+    pub fn put(&self, k: KRef, v: VRef) -> Result<(), RustyRocksError> {
+        self.kv.put(k, v)
+    }
+
+    pub fn delete(&self, k: KRef) -> Result<(), RustyRocksError> {
+        self.kv.delete(k)
+    }
+
+    /// Compact the whole database, dropping every expired entry.
+    pub fn compact_all(&self) {
+        self.kv.compact_all()
+    }
+
+    pub fn db_iter(&self) -> DBIter<'_, K, V>
+    where
+        K: Deserializable,
+    {
+        self.kv.db_iter()
+    }
+}
@@ -0,0 +1,95 @@
+//! Values tagged with a format version, migrated from older formats on read.
+
+use crate::{AssociateMergeable, Deserializable, MergePolicy, TrySerializable, VersionedError};
+
+/// A value type whose stored format has changed over time, and which can
+/// still read the formats it had before.
+///
+/// Wrap it in `Versioned` to store it with a leading version byte.
+pub trait VersionedValue: Deserializable {
+    /// The version written with new values, which `deserialize` reads.
+    fn current_version() -> u8;
+
+    /// Read `bytes` stored as version `from`, which is not the current one.
+    ///
+    /// This should fail for versions it does not know, including ones newer
+    /// than the current version.
+    fn migrate(from: u8, bytes: &[u8]) -> Result<Self, Self::Error>;
+}
+
+/// Stores a `T` after a byte holding its format version.
+///
+/// Reading a value stored with an older version goes through
+/// `T::migrate`. This includes every existing value and operand the merge
+/// operator reads, so a `MergeableDB` of `Versioned<T>` merges values of
+/// mixed versions, and rewrites them at the current one as it does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Versioned<T>(pub T);
+
+impl<'a, T> TrySerializable for &'a Versioned<T>
+where
+    T: VersionedValue,
+    &'a T: TrySerializable,
+{
+    type Bytes = Vec<u8>;
+    type Error = <&'a T as TrySerializable>::Error;
+
+    fn try_serialize(self) -> Result<Vec<u8>, Self::Error> {
+        let inner = (&self.0).try_serialize()?;
+        let mut bytes = Vec::with_capacity(inner.as_ref().len() + 1);
+        bytes.push(T::current_version());
+        bytes.extend_from_slice(inner.as_ref());
+        Ok(bytes)
+    }
+}
+
+impl<T: VersionedValue> Deserializable for Versioned<T> {
+    type Error = VersionedError<T::Error>;
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let (&version, rest) = bytes.split_first().ok_or(VersionedError::Untagged)?;
+        let value = if version == T::current_version() {
+            T::deserialize(rest)
+        } else {
+            T::migrate(version, rest)
+        };
+        value.map(Versioned).map_err(VersionedError::Value)
+    }
+}
+
+impl<T: VersionedValue + AssociateMergeable> AssociateMergeable for Versioned<T> {
+    fn merge(&mut self, other: Self) {
+        self.0.merge(other.0);
+    }
+
+    fn partial_merge(&mut self, other: Self) {
+        self.0.partial_merge(other.0);
+    }
+
+    fn settle(self) -> Self {
+        Versioned(self.0.settle())
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        let inner = self.0.into_bytes();
+        let mut bytes = Vec::with_capacity(inner.len() + 1);
+        bytes.push(T::current_version());
+        bytes.extend_from_slice(&inner);
+        bytes
+    }
+
+    fn handle_deser_error(
+        key: &[u8],
+        bytes: &[u8],
+        err: VersionedError<T::Error>,
+    ) -> MergePolicy<Self> {
+        match err {
+            VersionedError::Untagged => MergePolicy::Skip,
+            VersionedError::Value(err) => match T::handle_deser_error(key, &bytes[1..], err) {
+                MergePolicy::Skip => MergePolicy::Skip,
+                MergePolicy::Use(value) => MergePolicy::Use(Versioned(value)),
+                MergePolicy::Abort => MergePolicy::Abort,
+            },
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Reading back recent writes from the write-ahead log.
+
+use std::marker::PhantomData;
+
+use rocksdb::{DBWALIterator, WriteBatchIterator};
+
+use crate::{deserialize_key, deserialize_value, Deserializable, RustyRocksError};
+
+/// A single write read back from the write-ahead log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update<K, V> {
+    Put(K, V),
+    Delete(K),
+    /// How many of the batch's writes could not be read back, if any; always
+    /// the last update of its batch.
+    ///
+    /// rocksdb 0.19 only reports puts and deletes to the default column
+    /// family. Merges and single deletes are left out, and a range delete or
+    /// a write to another column family stops the batch being read, so
+    /// everything after it is left out too.
+    Undecoded(usize),
+}
+
+/// An iterator over the write batches in the write-ahead log, from
+/// `KeyValueDB::updates_since`.
+///
+/// Each item is the sequence number of a batch's first write, and the writes
+/// in it, in order.
+pub struct Updates<K, V> {
+    wal: DBWALIterator,
+    phantom: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> Updates<K, V> {
+    pub(crate) fn new(wal: DBWALIterator) -> Self {
+        Updates {
+            wal,
+            phantom: PhantomData,
+        }
+    }
+}
+
+type RawUpdate = Update<Box<[u8]>, Box<[u8]>>;
+
+// Gathers a batch's writes, still serialized.
+#[derive(Default)]
+struct Collect(Vec<RawUpdate>);
+
+impl WriteBatchIterator for Collect {
+    fn put(&mut self, key: Box<[u8]>, value: Box<[u8]>) {
+        self.0.push(Update::Put(key, value));
+    }
+
+    fn delete(&mut self, key: Box<[u8]>) {
+        self.0.push(Update::Delete(key));
+    }
+}
+
+impl<K: Deserializable, V: Deserializable> Iterator for Updates<K, V> {
+    type Item = Result<(u64, Vec<Update<K, V>>), RustyRocksError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (seq, batch) = match self.wal.next()? {
+            Ok(next) => next,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let mut raw = Collect::default();
+        batch.iterate(&mut raw);
+        let undecoded = batch.len() - raw.0.len();
+        if undecoded > 0 {
+            raw.0.push(Update::Undecoded(undecoded));
+        }
+        let updates: Result<Vec<_>, RustyRocksError> = raw
+            .0
+            .into_iter()
+            .map(|update| match update {
+                Update::Put(k, v) => Ok(Update::Put(
+                    deserialize_key(&k)?,
+                    deserialize_value(&k, &v)?,
+                )),
+                Update::Delete(k) => Ok(Update::Delete(deserialize_key(&k)?)),
+                Update::Undecoded(n) => Ok(Update::Undecoded(n)),
+            })
+            .collect();
+        Some(updates.map(|updates| (seq, updates)))
+    }
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "tokio")]
+
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn concurrent_async_gets_and_puts() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, String, &'static str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    let a = db.to_async();
+    let mut hs = vec![];
+    for i in 0..50u32 {
+        let a = a.clone();
+        hs.push(tokio::spawn(async move {
+            a.put(i, ["even", "odd"][i as usize % 2]).await.unwrap();
+            assert_eq!(
+                a.get(i).await.unwrap().unwrap(),
+                ["even", "odd"][i as usize % 2]
+            );
+        }));
+    }
+    for h in hs {
+        h.await.unwrap();
+    }
+    a.delete(3).await.unwrap();
+    assert!(a.get(3).await.unwrap().is_none());
+    assert!(a.merge(4, "x").await.is_err());
+    assert_eq!(db.get(10).unwrap().unwrap(), "even");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn async_handles_share_one_database() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, String, &'static str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    let a = db.to_async();
+    let writers: Vec<_> = (0..4u32)
+        .map(|t| {
+            let a = a.clone();
+            tokio::spawn(async move {
+                for i in 0..25 {
+                    a.put(t * 100 + i, "v").await.unwrap();
+                }
+            })
+        })
+        .collect();
+    for w in writers {
+        w.await.unwrap();
+    }
+    assert_eq!(db.db_iter().count(), 100);
+    assert_eq!(a.get(325).await.unwrap(), None);
+    assert_eq!(a.get(324).await.unwrap().as_deref(), Some("v"));
+}
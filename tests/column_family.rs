@@ -0,0 +1,265 @@
+mod common;
+
+use common::Words;
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[test]
+fn multi_get_cf_reads_across_column_families() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_cf(&p, ["users", "posts"]).unwrap();
+    db.put_cf("users", "1", "ada").unwrap();
+    db.put_cf("posts", "1", "hello").unwrap();
+    db.put("1", "default").unwrap();
+    let got = db
+        .multi_get_cf([
+            ("posts", "1"),
+            ("users", "1"),
+            ("users", "2"),
+            ("default", "1"),
+        ])
+        .unwrap();
+    assert_eq!(
+        got,
+        vec![
+            Some("hello".into()),
+            Some("ada".into()),
+            None,
+            Some("default".into())
+        ]
+    );
+    assert!(matches!(
+        db.get_cf("nope", "1"),
+        Err(RustyRocksError::UnknownColumnFamily(_))
+    ));
+    db.delete_cf("users", "1").unwrap();
+    assert_eq!(db.get_cf("users", "1").unwrap(), None);
+    drop(db);
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_cf(&p, ["users", "posts"]).unwrap();
+    assert_eq!(db.get_cf("posts", "1").unwrap().unwrap(), "hello");
+}
+
+#[test]
+fn column_families_with_their_own_compression() {
+    use rocksdb::DBCompressionType;
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_cf_descriptors(
+            &p,
+            vec![
+                ColumnFamilyBuilder::new("hot").compression(DBCompressionType::Lz4),
+                ColumnFamilyBuilder::new("cold").compression(DBCompressionType::Zstd),
+            ],
+        )
+        .unwrap();
+        db.put_cf("hot", "a", "1").unwrap();
+        db.put_cf("cold", "b", "2").unwrap();
+        db.flush().unwrap();
+    }
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_cf(&p, ["hot", "cold"]).unwrap();
+    assert_eq!(db.get_cf("hot", "a").unwrap().as_deref(), Some("1"));
+    assert_eq!(db.get_cf("cold", "b").unwrap().as_deref(), Some("2"));
+}
+
+#[test]
+fn write_batch_cf_is_atomic() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_cf(dir.path().join("db"), ["users", "emails"]).unwrap();
+    db.put_cf("emails", "old@x", "ada").unwrap();
+    db.write_batch_cf(vec![
+        ("users", BatchOp::Put("ada", "Ada Lovelace")),
+        ("emails", BatchOp::Put("ada@x", "ada")),
+        ("emails", BatchOp::Delete("old@x")),
+    ])
+    .unwrap();
+    assert_eq!(
+        db.get_cf("users", "ada").unwrap().as_deref(),
+        Some("Ada Lovelace")
+    );
+    assert_eq!(
+        db.get_cf("emails", "ada@x").unwrap().as_deref(),
+        Some("ada")
+    );
+    assert_eq!(db.get_cf("emails", "old@x").unwrap(), None);
+    let r = db.write_batch_cf(vec![
+        ("users", BatchOp::Put("bob", "Bob")),
+        ("nope", BatchOp::Delete("x")),
+    ]);
+    assert!(matches!(r, Err(RustyRocksError::UnknownColumnFamily(_))));
+    assert_eq!(db.get_cf("users", "bob").unwrap(), None);
+}
+
+#[test]
+fn iterate_a_column_family() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_cf(dir.path().join("db"), ["a", "b"]).unwrap();
+    for i in 0..10 {
+        db.put_cf("a", i, "x").unwrap();
+    }
+    db.put_cf("b", 99, "y").unwrap();
+    db.put(50, "z").unwrap();
+    let f: Vec<u32> = db
+        .db_iter_cf("a")
+        .unwrap()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(f, (0..10).collect::<Vec<_>>());
+    let r: Vec<u32> = db
+        .db_iter_cf_rev("a")
+        .unwrap()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(r, (0..10).rev().collect::<Vec<_>>());
+    let g: Vec<u32> = db
+        .db_iter_cf_range("a", 3, 7)
+        .unwrap()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(g, vec![3, 4, 5, 6]);
+    let gb: Vec<u32> = db
+        .db_iter_cf_range("a", 3, 7)
+        .unwrap()
+        .rev()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(gb, vec![6, 5, 4, 3]);
+    let b: Vec<u32> = db
+        .db_iter_cf("b")
+        .unwrap()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(b, vec![99]);
+    assert!(db.db_iter_cf("c").is_err());
+}
+
+#[test]
+fn with_value_cf_reads_in_place() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_cf(dir.path().join("db"), ["users"]).unwrap();
+    db.put_cf("users", "a", "alice").unwrap();
+    assert_eq!(
+        db.with_value_cf("users", "a", |b| b.len()).unwrap(),
+        Some(5)
+    );
+    assert_eq!(db.with_value_cf("users", "b", |b| b.len()).unwrap(), None);
+    assert!(db.with_value_cf("nope", "a", |b| b.len()).is_err());
+}
+
+#[test]
+fn atomic_flush_across_column_families() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let mut opts = rocksdb::Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    opts.set_atomic_flush(true);
+    {
+        let db: KeyValueDB<&str, String, &str> =
+            KeyValueDB::open_cf_with_opts(&p, opts, ["a", "b"]).unwrap();
+        db.put_cf("a", "x", "1").unwrap();
+        db.put_cf("b", "y", "2").unwrap();
+        db.put("z", "3").unwrap();
+        db.flush().unwrap();
+        let mut cfs: Vec<String> = db
+            .live_files()
+            .unwrap()
+            .into_iter()
+            .map(|f| f.column_family)
+            .collect();
+        cfs.sort();
+        assert_eq!(cfs, vec!["a", "b", "default"]);
+        db.sync().unwrap();
+    }
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_cf(&p, ["a", "b"]).unwrap();
+    assert_eq!(db.get_cf("a", "x").unwrap().as_deref(), Some("1"));
+    assert_eq!(db.get_cf("b", "y").unwrap().as_deref(), Some("2"));
+}
+
+#[test]
+fn mergeable_db_flushes_two_column_families() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let open = || {
+        MergeableDB::<&str, Words, &str>::builder()
+            .column_families(["a", "b"])
+            .atomic_flush(true)
+            .open(&p)
+            .unwrap()
+    };
+    let sst_files = || {
+        std::fs::read_dir(&p)
+            .unwrap()
+            .filter(|e| {
+                e.as_ref()
+                    .unwrap()
+                    .path()
+                    .extension()
+                    .is_some_and(|x| x == "sst")
+            })
+            .count()
+    };
+    {
+        let db = open();
+        db.put_cf("a", "k", "x").unwrap();
+        db.merge_cf("a", "k", "y").unwrap();
+        db.merge_cf("b", "k", "z").unwrap();
+        assert_eq!(sst_files(), 0);
+        db.flush().unwrap();
+        assert_eq!(sst_files(), 2);
+        assert_eq!(db.get_cf("a", "k").unwrap().unwrap().0, vec!["x", "y"]);
+        assert!(db.get("k").unwrap().is_none());
+        assert!(matches!(
+            db.merge_cf("c", "k", "w"),
+            Err(RustyRocksError::UnknownColumnFamily(_))
+        ));
+    }
+    let db = open();
+    db.merge_cf("b", "k", "w").unwrap();
+    assert_eq!(db.get_cf("a", "k").unwrap().unwrap().0, vec!["x", "y"]);
+    assert_eq!(db.get_cf("b", "k").unwrap().unwrap().0, vec!["z", "w"]);
+}
+
+#[test]
+fn list_cf_names_every_column_family() {
+    let dir = tempdir().unwrap();
+    type Db = KeyValueDB<&'static str, String, &'static str>;
+    let p = dir.path().join("db");
+    {
+        let db: Db = KeyValueDB::open_cf(&p, ["one", "two"]).unwrap();
+        db.put_cf("two", "k", "v").unwrap();
+    }
+    let mut names = Db::list_cf(&p).unwrap();
+    names.sort();
+    assert_eq!(names, ["default", "one", "two"]);
+    let db: Db = KeyValueDB::open_cf(&p, &names).unwrap();
+    assert_eq!(db.get_cf("two", "k").unwrap().as_deref(), Some("v"));
+    assert!(Db::list_cf(dir.path().join("missing")).is_err());
+}
+
+#[test]
+fn multi_with_values_cf() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_cf(dir.path().join("db"), ["cf"]).unwrap();
+    db.put_cf("cf", 1, "one").unwrap();
+    db.put_cf("cf", 2, "three").unwrap();
+    db.put_cf("cf", 3, "fives").unwrap();
+    db.put(4, "default").unwrap();
+    let mut total = 0;
+    let lens = db
+        .multi_with_values_cf("cf", vec![3, 9, 1, 4, 2], |b| {
+            total += b.len();
+            b[0]
+        })
+        .unwrap();
+    assert_eq!(lens, vec![Some(b'f'), None, Some(b'o'), None, Some(b't')]);
+    assert_eq!(total, 13);
+    assert!(db
+        .multi_with_values_cf("nope", vec![1], |b| b.len())
+        .is_err());
+}
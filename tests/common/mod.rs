@@ -0,0 +1,73 @@
+#![allow(dead_code)]
+
+use rustyrocks::*;
+
+#[derive(Debug)]
+pub struct BadErr;
+impl std::fmt::Display for BadErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "bad")
+    }
+}
+impl std::error::Error for BadErr {}
+
+#[derive(Debug, PartialEq)]
+pub struct Words(pub Vec<String>);
+impl Deserializable for Words {
+    type Error = BadErr;
+    fn deserialize(b: &[u8]) -> Result<Self, BadErr> {
+        if b.first() == Some(&b'!') {
+            return Err(BadErr);
+        }
+        Ok(Words(
+            std::str::from_utf8(b)
+                .unwrap()
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        ))
+    }
+}
+impl AssociateMergeable for Words {
+    fn merge(&mut self, o: Self) {
+        self.0.extend(o.0)
+    }
+    fn into_bytes(self) -> Vec<u8> {
+        self.0.join(",").into_bytes()
+    }
+    fn handle_deser_error(_k: &[u8], b: &[u8], _e: BadErr) -> MergePolicy<Self> {
+        match b[1] {
+            b's' => MergePolicy::Skip,
+            b'u' => MergePolicy::Use(Words(vec!["used".into()])),
+            _ => MergePolicy::Abort,
+        }
+    }
+}
+
+pub struct Last(pub String);
+impl Deserializable for Last {
+    type Error = std::str::Utf8Error;
+    fn deserialize(b: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Last(std::str::from_utf8(b)?.to_owned()))
+    }
+}
+impl AssociateMergeable for Last {
+    fn merge(&mut self, other: Self) {
+        *self = other;
+    }
+    fn into_bytes(self) -> Vec<u8> {
+        self.0.into_bytes()
+    }
+    fn handle_deser_error(_: &[u8], _: &[u8], _: Self::Error) -> MergePolicy<Self> {
+        MergePolicy::Abort
+    }
+}
+
+pub struct Boom;
+impl Deserializable for Boom {
+    type Error = LengthError;
+    fn deserialize(_: &[u8]) -> Result<Self, LengthError> {
+        panic!("deserialized")
+    }
+}
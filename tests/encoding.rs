@@ -0,0 +1,288 @@
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[test]
+fn signed_integer_keys_sort_numerically() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<i64, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    for k in [2i64, -1, 0, i64::MIN, 1, -2, i64::MAX] {
+        db.put(k, "v").unwrap();
+    }
+    let keys: Vec<i64> = db.db_iter().map(|kv| kv.unwrap().0).collect();
+    assert_eq!(keys, vec![i64::MIN, -2, -1, 0, 1, 2, i64::MAX]);
+    assert!(u32::deserialize(&[1, 2]).is_err());
+    assert_eq!(i32::deserialize(&(-5i32).serialize()).unwrap(), -5);
+}
+
+type Rec = (u32, String, Vec<i64>);
+
+#[test]
+fn bincode_values_round_trip() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<&str, Bincode<Rec>, &Bincode<Rec>> = KeyValueDB::open_default(&p).unwrap();
+    let r = Bincode((3, "x".to_string(), vec![-1, 2]));
+    db.put("k", &r).unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap(), r);
+    let db2: KeyValueDB<&str, Bincode<Rec>, Bincode<Rec>> =
+        KeyValueDB::open_default(dir.path().join("other")).unwrap();
+    db2.put("k", r.clone()).unwrap();
+    assert_eq!(db2.get("k").unwrap().unwrap(), r);
+}
+
+#[test]
+fn codecs_round_trip_with_their_own_encodings() {
+    fn round_trip<C: Codec<Rec>>(path: &std::path::Path) -> Vec<u8> {
+        let db: KeyValueDB<&str, Encoded<Rec, C>, &Encoded<Rec, C>> =
+            KeyValueDB::open_default(path).unwrap();
+        let r: Encoded<Rec, C> = Encoded::new((3, "x".to_string(), vec![-1, 2]));
+        db.put("k", &r).unwrap();
+        assert_eq!(db.get("k").unwrap().unwrap(), r);
+        db.with_value("k", |b| b.to_vec()).unwrap().unwrap()
+    }
+    let dir = tempdir().unwrap();
+    let m = round_trip::<MsgPackCodec>(&dir.path().join("msgpack"));
+    let b = round_trip::<BincodeCodec>(&dir.path().join("bincode"));
+    assert!(m != b);
+    #[cfg(feature = "json")]
+    {
+        let j = round_trip::<JsonCodec>(&dir.path().join("json"));
+        assert_eq!(j, br#"[3,"x",[-1,2]]"#.to_vec());
+        assert!(j != m && j != b);
+    }
+}
+
+#[test]
+fn byte_slices_round_trip() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<&[u8], Vec<u8>, &[u8]> = KeyValueDB::open_default(&p).unwrap();
+    db.put(b"a\0b", b"\0\xff\0").unwrap();
+    assert_eq!(db.get(b"a\0b").unwrap().unwrap(), b"\0\xff\0".to_vec());
+    let all: Vec<(Vec<u8>, Vec<u8>)> = db.db_iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(all.len(), 1);
+    let db2: KeyValueDB<Vec<u8>, Vec<u8>, Vec<u8>> =
+        KeyValueDB::open_default(dir.path().join("other")).unwrap();
+    db2.put(vec![0], vec![1]).unwrap();
+}
+
+#[test]
+#[allow(clippy::type_complexity)]
+fn tuple_keys_sort_by_component() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<(u64, u32), String, &str> = KeyValueDB::open_default(&p).unwrap();
+    db.put((1, 3), "b").unwrap();
+    db.put((1, 2), "a").unwrap();
+    db.put((0, 9), "z").unwrap();
+    let ks: Vec<(u64, u32)> = db.db_keys().collect::<Result<_, _>>().unwrap();
+    assert_eq!(ks, vec![(0, 9), (1, 2), (1, 3)]);
+    assert_eq!(db.get((1, 2)).unwrap().unwrap(), "a");
+    let db2: KeyValueDB<(i32, [u8; 2], u64, (u32, u32)), String, &str> =
+        KeyValueDB::open_default(dir.path().join("other")).unwrap();
+    db2.put((-1, *b"ab", 3, (4, 5)), "x").unwrap();
+    let ks: Vec<(i32, [u8; 2], u64, (u32, u32))> = db2.db_keys().collect::<Result<_, _>>().unwrap();
+    assert_eq!(ks, vec![(-1, *b"ab", 3, (4, 5))]);
+    assert!(<(u32, u32)>::deserialize(&[0; 7]).is_err());
+}
+
+#[test]
+fn owned_strings_as_keys_and_values() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<String, String, String> = KeyValueDB::open_default(&p).unwrap();
+    db.put("a".to_string(), format!("{}", 1)).unwrap();
+    assert_eq!(db.get("a".to_string()).unwrap().as_deref(), Some("1"));
+    let db2: KeyValueDB<&str, String, String> =
+        KeyValueDB::open_default(dir.path().join("other")).unwrap();
+    db2.put("k", "v".to_string()).unwrap();
+    assert_eq!(db2.get("k").unwrap().as_deref(), Some("v"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_values_round_trip() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, serde_json::Value, &serde_json::Value> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    let v = serde_json::json!({"a": [1, 2, {"b": null}], "c": "d"});
+    db.put("k", &v).unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap(), v);
+}
+
+#[test]
+fn bool_and_char_round_trip_and_reject_invalid_bytes() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<char, bool, bool> = KeyValueDB::open_default(&p).unwrap();
+    for (c, b) in [
+        ('a', true),
+        ('é', false),
+        ('漢', true),
+        ('🦀', false),
+        ('\0', true),
+    ] {
+        db.put(c, b).unwrap();
+        assert_eq!(db.get(c).unwrap(), Some(b));
+    }
+    let keys: Vec<char> = db.db_keys().map(Result::unwrap).collect();
+    assert_eq!(keys, vec!['\0', 'a', 'é', '漢', '🦀']);
+    drop(db);
+    let raw: KeyValueDB<&[u8], Vec<u8>, &[u8]> = KeyValueDB::open_default(&p).unwrap();
+    raw.put(&[0, 0, 0xd8, 0][..], &[1][..]).unwrap();
+    raw.put(&[0, 0, 0, 0x62][..], &[2][..]).unwrap();
+    drop(raw);
+    let db: KeyValueDB<char, bool, bool> = KeyValueDB::open_default(&p).unwrap();
+    let e = db.get('b').unwrap_err();
+    assert!(e.to_string().contains("invalid bool"), "{}", e);
+    let errs: Vec<String> = db
+        .db_iter()
+        .filter_map(|r| r.err())
+        .map(|e| e.to_string())
+        .collect();
+    assert!(
+        errs.iter()
+            .any(|e| e.contains("invalid char encoding 0x0000d800")),
+        "{:?}",
+        errs
+    );
+    assert!(bool::deserialize(&[1, 0]).is_err());
+    assert!(char::deserialize(&[0, 0x11, 0, 0]).is_err());
+}
+
+#[test]
+fn composite_keys_sort_by_component() {
+    let dir = tempdir().unwrap();
+    let a: CompositeKey = ["a", "bc"].iter().collect();
+    let b: CompositeKey = ["ab", "c"].iter().collect();
+    let sa = (&a).serialize();
+    let sb = (&b).serialize();
+    assert_ne!(sa, sb);
+    assert!(sa < sb);
+    let db: KeyValueDB<&CompositeKey, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    let keys: Vec<CompositeKey> = vec![
+        CompositeKey::new().push("ab").push("c"),
+        CompositeKey::new().push("a").push("bc"),
+        CompositeKey::new().push("a"),
+        CompositeKey::new().push("a\0").push("x"),
+        CompositeKey::new().push("a").push(""),
+        CompositeKey::new().push(b"\0\x01").push(b"\xff"),
+    ];
+    for k in &keys {
+        db.put(k, "v").unwrap();
+    }
+    let got: Vec<CompositeKey> = db.db_keys().map(Result::unwrap).collect();
+    let mut want = keys.clone();
+    want.sort();
+    assert_eq!(got, want);
+    let pre = CompositeKey::new().push("a");
+    let under: Vec<CompositeKey> = db
+        .db_iter_prefix(&pre)
+        .unwrap()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(under.len(), 3);
+    assert!(CompositeKey::deserialize(b"a\0").is_err());
+    assert!(CompositeKey::deserialize(b"a").is_err());
+    assert!(CompositeKey::deserialize(b"a\0\x02").is_err());
+}
+
+#[test]
+fn float_keys_sort_numerically() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<f64, u32, u32> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    let vals = [
+        f64::INFINITY,
+        1.5,
+        -0.0,
+        0.0,
+        -1.5,
+        f64::NEG_INFINITY,
+        1e-300,
+        -1e300,
+    ];
+    for (i, v) in vals.iter().enumerate() {
+        db.put(*v, i as u32).unwrap();
+    }
+    let keys: Vec<f64> = db.db_keys().map(Result::unwrap).collect();
+    let mut want = vals.to_vec();
+    want.sort_by(|a, b| a.total_cmp(b));
+    assert_eq!(
+        keys.iter().map(|f| f.to_bits()).collect::<Vec<_>>(),
+        want.iter().map(|f| f.to_bits()).collect::<Vec<_>>()
+    );
+    let nan = f32::NAN;
+    let b = nan.serialize();
+    assert!(f32::deserialize(&b).unwrap().is_nan());
+    assert!((-2.0f32).serialize() < (-1.0f32).serialize());
+    assert!((f32::INFINITY).serialize() < nan.serialize());
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn uuid_keys_sort_bytewise() {
+    use uuid::Uuid;
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<Uuid, u32, u32> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    let mut ids: Vec<Uuid> = [
+        "ffffffff-0000-0000-0000-000000000000",
+        "00000000-0000-0000-0000-000000000001",
+        "7fffffff-ffff-ffff-ffff-ffffffffffff",
+        "80000000-0000-0000-0000-000000000000",
+    ]
+    .iter()
+    .map(|s| Uuid::parse_str(s).unwrap())
+    .collect();
+    for (i, id) in ids.iter().enumerate() {
+        db.put(*id, i as u32).unwrap();
+    }
+    for (i, id) in ids.iter().enumerate() {
+        assert_eq!(db.get(*id).unwrap(), Some(i as u32));
+    }
+    ids.sort();
+    let keys: Vec<Uuid> = db.db_keys().map(|k| k.unwrap()).collect();
+    assert_eq!(keys, ids);
+    let db2: KeyValueDB<(Uuid, u32), u32, u32> =
+        KeyValueDB::open_default(dir.path().join("tuples")).unwrap();
+    db2.put((ids[0], 3), 1).unwrap();
+    assert_eq!(db2.db_keys().next().unwrap().unwrap(), (ids[0], 3));
+}
+
+#[test]
+fn serialize_into_appends_the_encoding() {
+    let dir = tempdir().unwrap();
+    let mut buf = vec![9u8];
+    (1u32, -2i64).serialize_into(&mut buf);
+    let mut expect = vec![9u8];
+    expect.extend((1u32, -2i64).serialize());
+    assert_eq!(buf, expect);
+    let ck: CompositeKey = vec![b"a\0b".to_vec(), vec![]].into_iter().collect();
+    let mut b2 = Vec::new();
+    (&ck).serialize_into(&mut b2);
+    assert_eq!(b2, (&ck).serialize());
+    let mut b3 = Vec::new();
+    "hi".try_serialize_into(&mut b3).unwrap();
+    assert_eq!(b3, b"hi");
+    let db: KeyValueDB<(u32, u32), String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.put_sorted(vec![((1, 2), "a"), ((1, 3), "bb"), ((2, 0), "c")])
+        .unwrap();
+    assert!(matches!(
+        db.checked_put_sorted(vec![((5, 0), "x"), ((4, 0), "y")]),
+        Err(RustyRocksError::KeysOutOfOrder { index: 1 })
+    ));
+    db.checked_put_sorted(vec![((7, 0), "x"), ((7, 0), "y")])
+        .unwrap();
+    let all: Vec<((u32, u32), String)> = db.db_iter().map(|kv| kv.unwrap()).collect();
+    assert_eq!(
+        all,
+        vec![
+            ((1, 2), "a".into()),
+            ((1, 3), "bb".into()),
+            ((2, 0), "c".into()),
+            ((7, 0), "y".into())
+        ]
+    );
+}
@@ -0,0 +1,70 @@
+// The example's own types, so that these tests exercise its trait impls.
+#[allow(dead_code)]
+#[path = "../examples/helloworld.rs"]
+mod helloworld;
+
+use helloworld::BSet;
+use rustyrocks::*;
+use tempfile::tempdir;
+
+fn set(words: &[&str]) -> BSet<String> {
+    BSet(words.iter().map(|&w| w.to_owned()).collect())
+}
+
+#[test]
+fn sets_round_trip() {
+    let s = set(&["b", "a", "c"]);
+    let bytes = (&s).try_serialize().unwrap();
+    let back = BSet::<String>::deserialize(&bytes).unwrap();
+    assert_eq!(back.0, s.0);
+    assert!(BSet::<String>::deserialize(&[0xc1]).is_err());
+    assert!(BSet::<String>::deserialize(&[]).unwrap().0.is_empty());
+}
+
+#[test]
+fn merges_collect_the_union() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, BSet<String>, &BSet<String>> =
+        MergeableDB::new(dir.path().join("db")).unwrap();
+    db.merge_owned("fruit", set(&["pear"])).unwrap();
+    db.merge_owned("fruit", set(&["apple", "pear"])).unwrap();
+    db.merge("fruit", &set(&["fig"])).unwrap();
+    db.merge_owned("veg", set(&["kale"])).unwrap();
+    let all: Vec<(String, Vec<String>)> = db
+        .db_iter()
+        .map(|kv| {
+            let (k, v) = kv.unwrap();
+            (k, v.0.into_iter().collect())
+        })
+        .collect();
+    assert_eq!(
+        all,
+        vec![
+            (
+                "fruit".into(),
+                vec!["apple".into(), "fig".into(), "pear".into()]
+            ),
+            ("veg".into(), vec!["kale".into()]),
+        ]
+    );
+}
+
+#[test]
+fn corrupt_operands_are_skipped() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let raw: MergeableDB<&str, BSet<String>, &[u8]> = MergeableDB::new(&p).unwrap();
+        raw.merge("k", &[0xc1][..]).unwrap();
+    }
+    let db: MergeableDB<&str, BSet<String>, &BSet<String>> = MergeableDB::new(&p).unwrap();
+    let skipped = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = skipped.clone();
+    db.set_error_handler(move |_| {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    });
+    db.merge("k", &set(&["ok"])).unwrap();
+    let got = db.get("k").unwrap().unwrap();
+    assert_eq!(got.0.into_iter().collect::<Vec<_>>(), vec!["ok"]);
+    assert!(skipped.load(std::sync::atomic::Ordering::SeqCst) >= 1);
+}
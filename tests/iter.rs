@@ -0,0 +1,566 @@
+mod common;
+
+use common::Boom;
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[test]
+fn keys_and_values_match_full_iteration() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    for (k, v) in [(3u32, "c"), (1, "a"), (2, "b")] {
+        db.put(k, v).unwrap();
+    }
+    let full: Vec<(u32, String)> = db.db_iter().collect::<Result<_, _>>().unwrap();
+    let keys: Vec<u32> = db.db_keys().collect::<Result<_, _>>().unwrap();
+    let vals: Vec<String> = db.db_values().collect::<Result<_, _>>().unwrap();
+    assert_eq!(full.iter().map(|kv| kv.0).collect::<Vec<_>>(), keys);
+    assert_eq!(full.into_iter().map(|kv| kv.1).collect::<Vec<_>>(), vals);
+}
+
+#[test]
+fn iteration_is_double_ended() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    for k in 1..=5u32 {
+        db.put(k, "v").unwrap();
+    }
+    let mut it = db.db_iter();
+    let mut seen = vec![];
+    loop {
+        match it.next() {
+            Some(kv) => seen.push(kv.unwrap().0),
+            None => break,
+        }
+        match it.next_back() {
+            Some(kv) => seen.push(kv.unwrap().0),
+            None => break,
+        }
+    }
+    assert_eq!(seen, vec![1, 5, 2, 4, 3]);
+    assert!(it.next().is_none() && it.next_back().is_none());
+    let rev: Vec<u32> = db.db_iter().rev().map(|kv| kv.unwrap().0).collect();
+    assert_eq!(rev, vec![5, 4, 3, 2, 1]);
+    let it = db.db_iter();
+    db.put(9, "v").unwrap();
+    assert_eq!(it.count(), 5);
+    let empty: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_default(dir.path().join("empty")).unwrap();
+    assert!(empty.db_iter().next_back().is_none());
+}
+
+#[test]
+fn iterate_from_a_key_in_either_direction() {
+    use rocksdb::Direction;
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    for k in (0..10u32).step_by(2) {
+        db.put(k, "v").unwrap();
+    }
+    let keys = |s, d| -> Vec<u32> {
+        db.db_iter_from(s, d)
+            .unwrap()
+            .map(|kv| kv.unwrap().0)
+            .collect()
+    };
+    assert_eq!(keys(4, Direction::Forward), vec![4, 6, 8]);
+    assert_eq!(keys(5, Direction::Forward), vec![6, 8]);
+    assert_eq!(keys(4, Direction::Reverse), vec![4, 2, 0]);
+    assert_eq!(keys(5, Direction::Reverse), vec![4, 2, 0]);
+    assert_eq!(keys(9, Direction::Forward), Vec::<u32>::new());
+    let back: Vec<u32> = db
+        .db_iter_from(5, Direction::Reverse)
+        .unwrap()
+        .rev()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(back, vec![0, 2, 4]);
+    let fwd_back: Vec<u32> = db
+        .db_iter_from(3, Direction::Forward)
+        .unwrap()
+        .rev()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(fwd_back, vec![8, 6, 4]);
+    let mut it = db.db_iter_from(3, Direction::Forward).unwrap();
+    assert_eq!(it.next().unwrap().unwrap().0, 4);
+    assert_eq!(it.next_back().unwrap().unwrap().0, 8);
+    assert_eq!(it.next().unwrap().unwrap().0, 6);
+    assert!(it.next_back().is_none());
+}
+
+#[test]
+fn prefix_iteration_with_and_without_prefix_extractor() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_with_prefix(&p, 2).unwrap();
+    for k in ["aa1", "aa2", "ab1", "ab2", "ab3", "ac1", "b"] {
+        db.put(k, "v").unwrap();
+    }
+    for flush in [false, true] {
+        if flush {
+            db.flush().unwrap();
+        }
+        let ks: Vec<String> = db
+            .db_iter_prefix("ab")
+            .unwrap()
+            .map(|kv| kv.unwrap().0)
+            .collect();
+        assert_eq!(ks, vec!["ab1", "ab2", "ab3"]);
+        let ks: Vec<String> = db
+            .db_iter_prefix("ab")
+            .unwrap()
+            .rev()
+            .map(|kv| kv.unwrap().0)
+            .collect();
+        assert_eq!(ks, vec!["ab3", "ab2", "ab1"]);
+        let ks: Vec<String> = db
+            .db_iter_prefix("a")
+            .unwrap()
+            .map(|kv| kv.unwrap().0)
+            .collect();
+        assert_eq!(ks.len(), 6);
+        assert_eq!(db.db_iter_prefix("zz").unwrap().count(), 0);
+        let ks: Vec<String> = db
+            .db_iter_from("ab5", rocksdb::Direction::Forward)
+            .unwrap()
+            .map(|kv| kv.unwrap().0)
+            .collect();
+        assert_eq!(ks, vec!["ac1", "b"]);
+    }
+    let p2 = dir.path().join("no_extractor");
+    let db2: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p2).unwrap();
+    db2.put("\u{7f}", "v").unwrap();
+    assert_eq!(db2.db_iter_prefix("\u{7f}").unwrap().count(), 1);
+}
+
+#[test]
+fn read_options_are_applied() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    for k in 0..100u32 {
+        db.put(k, "v").unwrap();
+    }
+    db.flush().unwrap();
+    let n = db
+        .db_iter_opt(|o| {
+            o.fill_cache(false);
+            o.set_verify_checksums(false);
+        })
+        .count();
+    assert_eq!(n, 100);
+    let ks: Vec<u32> = db
+        .db_iter_opt(|o| o.set_iterate_upper_bound(5u32.to_be_bytes()))
+        .rev()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(ks, vec![4, 3, 2, 1, 0]);
+    assert_eq!(
+        db.get_opt(3, |o| o.fill_cache(false)).unwrap().unwrap(),
+        "v"
+    );
+}
+
+#[test]
+fn iteration_over_a_corrupt_block_ends_with_an_error() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let v = "abcdefgh".repeat(50);
+    {
+        let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        for i in 0..2000u32 {
+            db.put(i, v.as_str()).unwrap();
+        }
+        db.flush().unwrap();
+        let all: Vec<(u32, String)> = db.db_iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(all.len(), 2000);
+        assert!(all.iter().zip(0..).all(|((k, s), i)| *k == i && *s == v));
+    }
+    let sst = std::fs::read_dir(&p)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == "sst"))
+        .unwrap();
+    let mut bytes = std::fs::read(&sst).unwrap();
+    let mid = bytes.len() / 3;
+    for b in &mut bytes[mid..mid + 16] {
+        *b ^= 0xff;
+    }
+    std::fs::write(&sst, bytes).unwrap();
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    let mut it = db.db_iter();
+    let mut read = 0;
+    let err = loop {
+        match it.next() {
+            Some(Ok(_)) => read += 1,
+            Some(Err(e)) => break e,
+            None => panic!("iterated all {} entries without an error", read),
+        }
+    };
+    assert!(read < 2000);
+    assert!(matches!(err, RustyRocksError::RocksDb(_)), "{:?}", err);
+    assert!(it.next().is_none());
+}
+
+#[test]
+fn iteration_errors_carry_the_bytes() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let raw: KeyValueDB<&[u8], Vec<u8>, &[u8]> = KeyValueDB::open_default(&p).unwrap();
+        raw.put(b"\0\0\0\x01", b"\xff\xfe").unwrap();
+        raw.put(b"\x05", b"ok").unwrap();
+    }
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    let errs: Vec<RustyRocksError> = db.db_iter().filter_map(Result::err).collect();
+    match &errs[0] {
+        RustyRocksError::ValueDeserialize { key, bytes, .. } => {
+            assert_eq!(key, &[0, 0, 0, 1]);
+            assert_eq!(bytes, &[0xff, 0xfe]);
+        }
+        e => panic!("{:?}", e),
+    }
+    match &errs[1] {
+        RustyRocksError::KeyDeserialize { bytes, .. } => assert_eq!(bytes, &[5]),
+        e => panic!("{:?}", e),
+    }
+    assert!(errs[1].to_string().contains("0x05"), "{}", errs[1]);
+}
+
+#[test]
+fn skip_errors_reports_and_skips() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let raw: KeyValueDB<&[u8], Vec<u8>, &[u8]> = KeyValueDB::open_default(&p).unwrap();
+        for i in 0..6u8 {
+            let v: &[u8] = if i % 3 == 0 { b"\xff" } else { b"ok" };
+            raw.put(&[b'a' + i], v).unwrap();
+        }
+    }
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    let mut skipped = 0;
+    let keys: Vec<String> = db
+        .db_iter()
+        .skip_errors(|_| skipped += 1)
+        .map(|kv| kv.0)
+        .collect();
+    assert_eq!(keys, vec!["b", "c", "e", "f"]);
+    assert_eq!(skipped, 2);
+    let back: Vec<String> = db
+        .db_iter()
+        .skip_errors(|_| ())
+        .rev()
+        .map(|kv| kv.0)
+        .collect();
+    assert_eq!(back, vec!["f", "e", "c", "b"]);
+}
+
+#[test]
+fn scan_prefix_suffixes_strips_the_prefix() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for k in ["dir/a", "dir/b", "dia", "dir0", "e/x"] {
+        db.put(k, k).unwrap();
+    }
+    let got: Vec<(String, String)> = db
+        .scan_prefix_suffixes::<String>("dir/")
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        got,
+        vec![("a".into(), "dir/a".into()), ("b".into(), "dir/b".into())]
+    );
+}
+
+#[test]
+fn owned_iter_outlives_the_database_handle() {
+    let dir = tempdir().unwrap();
+    let iter = {
+        let db: KeyValueDB<u32, u64, u64> =
+            KeyValueDB::open_default(dir.path().join("db")).unwrap();
+        for i in 0..1000u32 {
+            db.put(i, i as u64 * 2).unwrap();
+        }
+        db.owned_iter()
+    };
+    let got: Vec<(u32, u64)> = std::thread::spawn(move || iter.map(Result::unwrap).collect())
+        .join()
+        .unwrap();
+    assert_eq!(got.len(), 1000);
+    assert!(got
+        .iter()
+        .enumerate()
+        .all(|(i, &(k, v))| k == i as u32 && v == 2 * k as u64));
+}
+
+#[test]
+fn scan_matches_default_iteration() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for i in 0..5000 {
+        db.put(i, &format!("v{}", i)).unwrap();
+    }
+    db.flush().unwrap();
+    let a: Vec<(u32, String)> = db.db_iter().map(Result::unwrap).collect();
+    let b: Vec<(u32, String)> = db.db_iter_scan(2 << 20).map(Result::unwrap).collect();
+    assert_eq!(a, b);
+    let c: Vec<u32> = db
+        .db_iter_scan(1 << 20)
+        .rev()
+        .take(2)
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(c, vec![4999, 4998]);
+}
+
+#[test]
+fn count_by_prefix() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for i in 0..3 {
+        db.put(&format!("a/{}", i), "").unwrap();
+    }
+    for i in 0..5 {
+        db.put(&format!("b/{}", i), "").unwrap();
+    }
+    db.put("c", "").unwrap();
+    db.put("b", "").unwrap();
+    assert_eq!(
+        db.count_by_prefix(&["a/", "b/", "z/", "b"]).unwrap(),
+        vec![3, 5, 0, 6]
+    );
+}
+
+#[test]
+fn db_iter_matches_the_rocksdb_iterator() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        for i in (0..3000u32).step_by(2) {
+            db.put(i, &format!("v{}", i)).unwrap();
+        }
+        db.flush().unwrap();
+        for i in (1..3000u32).step_by(2) {
+            db.put(i, &format!("w{}", i)).unwrap();
+        }
+        db.delete(10).unwrap();
+    }
+    let expected: Vec<(u32, String)> = {
+        let raw = rocksdb::DB::open_default(&p).unwrap();
+        raw.iterator(rocksdb::IteratorMode::Start)
+            .map(|kv| {
+                let (k, v) = kv.unwrap();
+                (
+                    u32::deserialize(&k).unwrap(),
+                    String::deserialize(&v).unwrap(),
+                )
+            })
+            .collect()
+    };
+    assert_eq!(expected.len(), 2999);
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    let got: Vec<(u32, String)> = db.db_iter().map(Result::unwrap).collect();
+    assert_eq!(got, expected);
+    let mut back: Vec<(u32, String)> = db.db_iter().rev().map(Result::unwrap).collect();
+    back.reverse();
+    assert_eq!(back, expected);
+}
+
+static DESERIALIZED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+struct Counted(u32);
+impl Deserializable for Counted {
+    type Error = LengthError;
+    fn deserialize(b: &[u8]) -> Result<Self, LengthError> {
+        DESERIALIZED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(Counted(u32::deserialize(b)?))
+    }
+}
+
+#[test]
+fn iter_while_stops_at_the_first_rejected_key() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, Counted, u32> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for i in 0..100u32 {
+        db.put(i, i).unwrap();
+    }
+    DESERIALIZED.store(0, std::sync::atomic::Ordering::SeqCst);
+    let got = db.iter_while(|k, _| *k < 10).unwrap();
+    assert_eq!(got.len(), 10);
+    assert_eq!(got[9].1 .0, 9);
+    assert_eq!(DESERIALIZED.load(std::sync::atomic::Ordering::SeqCst), 11);
+}
+
+#[test]
+fn projections_skip_deserializing() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, Counted, u32> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for i in 0..20u32 {
+        db.put(i, i * 2).unwrap();
+    }
+    let keys: Vec<u32> = db.db_keys().map(Result::unwrap).collect();
+    assert_eq!(keys, (0..20).collect::<Vec<_>>());
+    let rev: Vec<u32> = db.db_iter().keys().rev().map(Result::unwrap).collect();
+    assert_eq!(rev, (0..20).rev().collect::<Vec<_>>());
+    let vals: Vec<u32> = db.db_values().map(|v| v.unwrap().0).collect();
+    let pairs: Vec<(u32, u32)> = db
+        .db_iter()
+        .map(|kv| {
+            let (k, v) = kv.unwrap();
+            (k, v.0)
+        })
+        .collect();
+    assert_eq!(
+        pairs,
+        keys.iter()
+            .copied()
+            .zip(vals.iter().copied())
+            .collect::<Vec<_>>()
+    );
+    let p: DBIter<'_, u32, Counted, KeysOnly> = db
+        .db_iter_from(5, rocksdb::Direction::Forward)
+        .unwrap()
+        .project();
+    assert_eq!(p.count(), 15);
+    let bdb: KeyValueDB<u32, Boom, u32> =
+        KeyValueDB::open_default(dir.path().join("boom_values")).unwrap();
+    bdb.put(1, 1).unwrap();
+    assert_eq!(
+        bdb.db_keys().map(Result::unwrap).collect::<Vec<_>>(),
+        vec![1]
+    );
+    let vdb: KeyValueDB<u32, u32, u32, Boom> =
+        KeyValueDB::open_default(dir.path().join("boom_keys")).unwrap();
+    vdb.put(1, 7).unwrap();
+    assert_eq!(
+        vdb.db_values().map(Result::unwrap).collect::<Vec<_>>(),
+        vec![7]
+    );
+}
+
+#[test]
+fn page_through_keys() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, u32, u32> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for i in 0..95u32 {
+        db.put(i * 2, i).unwrap();
+    }
+    let mut seen = Vec::new();
+    let mut after = None;
+    let mut pages = 0;
+    loop {
+        let (items, cursor) = db.page(after, 10).unwrap();
+        pages += 1;
+        seen.extend(items.into_iter().map(|(k, _)| k));
+        match cursor {
+            Some(c) => after = Some(c),
+            None => break,
+        }
+    }
+    assert_eq!(pages, 10);
+    assert_eq!(seen, (0..95u32).map(|i| i * 2).collect::<Vec<_>>());
+    let (items, cursor) = db.page(Some(3), 2).unwrap();
+    assert_eq!(items, vec![(4, 2), (6, 3)]);
+    assert_eq!(cursor, Some(6));
+    let e: KeyValueDB<u32, u32, u32> = KeyValueDB::open_default(dir.path().join("exact")).unwrap();
+    for i in 0..10u32 {
+        e.put(i, i).unwrap();
+    }
+    let (items, cursor) = e.page(None, 10).unwrap();
+    assert_eq!((items.len(), cursor), (10, None));
+}
+
+#[test]
+fn reverse_prefix_iteration() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for k in ["p/1", "p/2", "p/3", "q/1", "o/9"] {
+        db.put(k, k).unwrap();
+    }
+    let keys: Vec<String> = db
+        .db_iter_prefix_rev("p/")
+        .unwrap()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(keys, vec!["p/3", "p/2", "p/1"]);
+    let back: Vec<String> = db
+        .db_iter_prefix_rev("p/")
+        .unwrap()
+        .rev()
+        .map(|kv| kv.unwrap().0)
+        .collect();
+    assert_eq!(back, vec!["p/1", "p/2", "p/3"]);
+    let mut it = db.db_iter_prefix_rev("p/").unwrap();
+    assert_eq!(it.next().unwrap().unwrap().0, "p/3");
+    assert_eq!(it.next_back().unwrap().unwrap().0, "p/1");
+    assert_eq!(it.next().unwrap().unwrap().0, "p/2");
+    assert!(it.next().is_none() && it.next_back().is_none());
+    assert_eq!(db.db_iter_prefix_rev("z").unwrap().count(), 0);
+}
+
+#[test]
+fn iterate_with_an_iterator_mode() {
+    use rocksdb::{Direction, IteratorMode};
+    let dir = tempdir().unwrap();
+    type Db = KeyValueDB<&'static str, String, &'static str>;
+    let db: Db = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for k in ["a", "b", "c", "d"] {
+        db.put(k, k).unwrap();
+    }
+    let keys = |it: DBIter<'_, String, String>| it.map(|kv| kv.unwrap().0).collect::<Vec<_>>();
+    assert_eq!(
+        keys(db.db_iter_mode(IteratorMode::Start)),
+        ["a", "b", "c", "d"]
+    );
+    assert_eq!(
+        keys(db.db_iter_mode(IteratorMode::End)),
+        ["d", "c", "b", "a"]
+    );
+    let bb = Db::serialize_key("bb").unwrap();
+    assert_eq!(
+        keys(db.db_iter_mode(IteratorMode::From(&bb, Direction::Forward))),
+        ["c", "d"]
+    );
+    assert_eq!(
+        keys(db.db_iter_mode(IteratorMode::From(&bb, Direction::Reverse))),
+        ["b", "a"]
+    );
+    let b = Db::serialize_key("b").unwrap();
+    assert_eq!(
+        keys(db.db_iter_mode(IteratorMode::From(&b, Direction::Reverse))),
+        ["b", "a"]
+    );
+}
+
+#[test]
+fn count_prefix_matches_manual_count() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_with_prefix(dir.path().join("db"), 2).unwrap();
+    for k in ["aa1", "aa2", "ab1", "ab", "b", "aa", "ac9", "aa\u{7f}"] {
+        db.put(k, k).unwrap();
+    }
+    db.flush().unwrap();
+    db.put("aa3", "x").unwrap();
+    for prefix in ["aa", "ab", "a", "b", "c", "aa1", ""] {
+        let manual = db
+            .db_iter()
+            .filter(|kv| kv.as_ref().unwrap().0.starts_with(prefix))
+            .count();
+        assert_eq!(db.count_prefix(prefix).unwrap(), manual, "{}", prefix);
+    }
+    assert_eq!(db.count_prefix("aa").unwrap(), 5);
+    assert_eq!(db.count_by_prefix(&["aa", "b"]).unwrap(), vec![5, 1]);
+}
@@ -0,0 +1,541 @@
+mod common;
+
+use common::{BadErr, Boom, Words};
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[test]
+fn get_borrowed_and_with_value_read_in_place() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    db.put("a", "hello").unwrap();
+    let len = db.get_borrowed::<str, _, _>("a", |s| s.len()).unwrap();
+    assert_eq!(len, Some(5));
+    assert_eq!(
+        db.with_value("a", |b| b.to_vec()).unwrap(),
+        Some(b"hello".to_vec())
+    );
+    assert_eq!(db.get("a").unwrap(), Some("hello".to_string()));
+    assert_eq!(db.get("b").unwrap(), None);
+    let all: Vec<(String, String)> = db.db_iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(all, vec![("a".into(), "hello".into())]);
+}
+
+#[test]
+fn boxed_errors_are_reported_as_sources() {
+    use std::error::Error;
+    let inner = || std::io::Error::new(std::io::ErrorKind::InvalidData, "truncated");
+    for e in [
+        RustyRocksError::deserialize(inner()),
+        RustyRocksError::serialize(inner()),
+    ] {
+        let source = e.source().expect("no source");
+        assert_eq!(source.to_string(), "truncated");
+        assert!(e.to_string().ends_with("truncated"), "{}", e);
+    }
+}
+
+struct Bad;
+impl TrySerializable for Bad {
+    type Bytes = Vec<u8>;
+    type Error = BadErr;
+    fn try_serialize(self) -> Result<Vec<u8>, BadErr> {
+        Err(BadErr)
+    }
+}
+
+#[test]
+fn failed_serialization_is_an_error() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<&str, String, Bad> = KeyValueDB::open_default(&p).unwrap();
+    match db.put("a", Bad) {
+        Err(RustyRocksError::Serialize(_)) => {}
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn snapshot_reads_do_not_see_later_writes() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    db.put("a", "old").unwrap();
+    let snap = db.snapshot();
+    db.put("a", "new").unwrap();
+    db.put("b", "x").unwrap();
+    assert_eq!(snap.get("a").unwrap().unwrap(), "old");
+    assert_eq!(snap.db_iter().count(), 1);
+    assert_eq!(db.get("a").unwrap().unwrap(), "new");
+}
+
+#[test]
+fn read_only_databases_refuse_writes() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        db.put("a", "b").unwrap();
+    }
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_read_only(&p, false).unwrap();
+    assert_eq!(db.get("a").unwrap().unwrap(), "b");
+    assert!(matches!(db.put("a", "c"), Err(RustyRocksError::ReadOnly)));
+    let p2 = dir.path().join("merge");
+    {
+        let db: MergeableDB<&str, Words, &str> = MergeableDB::new(&p2).unwrap();
+        db.merge("k", "a").unwrap();
+        db.merge("k", "b").unwrap();
+    }
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::open_read_only(&p2, false).unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap().0, vec!["a", "b"]);
+    assert!(matches!(db.merge("k", "c"), Err(RustyRocksError::ReadOnly)));
+}
+
+#[test]
+fn get_or_insert_with_inserts_once() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert_eq!(db.get_or_insert_with(1, || "a").unwrap(), "a");
+    assert_eq!(db.get_or_insert_with(1, || panic!()).unwrap(), "a");
+    assert_eq!(db.get(1).unwrap().unwrap(), "a");
+}
+
+#[test]
+fn delete_range_is_half_open() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    for k in 0..10u32 {
+        db.put(k, "v").unwrap();
+    }
+    db.delete_range(1, 9).unwrap();
+    let keys: Vec<u32> = db.db_keys().collect::<Result<_, _>>().unwrap();
+    assert_eq!(keys, vec![0, 9]);
+}
+
+#[test]
+fn put_if_absent_keeps_existing_values() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert!(db.put_if_absent(1, "a").unwrap());
+    assert!(!db.put_if_absent(1, "b").unwrap());
+    assert_eq!(db.get(1).unwrap().unwrap(), "a");
+}
+
+#[test]
+fn count_and_fold_values() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, u64, u64> = KeyValueDB::open_default(&p).unwrap();
+    for k in 0..10u32 {
+        db.put(k, k as u64 * 2).unwrap();
+    }
+    assert_eq!(db.count().unwrap(), 10);
+    assert_eq!(db.fold_values(0, |a, v| a + v).unwrap(), 90);
+}
+
+#[test]
+fn write_options_are_applied() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(&p).unwrap();
+    db.merge_opt("k", "a", |o| o.disable_wal(true)).unwrap();
+    db.merge_opt("k", "b", |o| o.set_sync(true)).unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap().0, vec!["a", "b"]);
+    let kv: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("kv")).unwrap();
+    kv.put_opt("a", "1", |o| o.disable_wal(true)).unwrap();
+    assert_eq!(kv.get("a").unwrap().unwrap(), "1");
+    kv.delete_opt("a", |o| o.set_sync(true)).unwrap();
+    assert!(kv.get("a").unwrap().is_none());
+}
+
+#[test]
+fn clear_removes_every_key() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    db.clear().unwrap();
+    for k in 0..1000u32 {
+        db.put(k, "v").unwrap();
+    }
+    db.put(u32::MAX, "v").unwrap();
+    db.clear().unwrap();
+    assert!(db.is_empty().unwrap());
+    db.put(5, "v").unwrap();
+    assert_eq!(db.count().unwrap(), 1);
+}
+
+#[test]
+fn merge_raw_uses_the_installed_operator() {
+    let dir = tempdir().unwrap();
+    let mut o = rocksdb::Options::default();
+    o.create_if_missing(true);
+    o.set_merge_operator_associative("append", |_k, ex, ops| {
+        let mut out = ex.map(|e| e.to_vec()).unwrap_or_default();
+        for op in ops {
+            out.extend_from_slice(op);
+        }
+        Some(out)
+    });
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_with_opts(dir.path().join("db"), o).unwrap();
+    db.put("k", "a").unwrap();
+    db.merge_raw("k", "b").unwrap();
+    db.merge_raw("k", "c").unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap(), "abc");
+    let plain: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("plain")).unwrap();
+    assert!(plain.merge_raw("k", "x").is_err());
+}
+
+#[test]
+fn first_and_last() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<i32, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    assert!(db.first().unwrap().is_none());
+    for k in [5, -3, 10, 0] {
+        db.put(k, "v").unwrap();
+    }
+    assert_eq!(db.first().unwrap().unwrap().0, -3);
+    assert_eq!(db.last().unwrap().unwrap().0, 10);
+}
+
+#[test]
+fn retain_deletes_rejected_values() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, u64, u64> = KeyValueDB::open_default(&p).unwrap();
+    for i in 0..10u32 {
+        db.put(i, i as u64 * 3).unwrap();
+    }
+    assert_eq!(db.retain(|_, v| v % 2 == 0).unwrap(), 5);
+    let vals: Vec<u64> = db.db_values().map(Result::unwrap).collect();
+    assert_eq!(vals, vec![0, 6, 12, 18, 24]);
+}
+
+#[test]
+fn get_or_falls_back_only_when_missing() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let raw: KeyValueDB<&[u8], Vec<u8>, &[u8]> = KeyValueDB::open_default(&p).unwrap();
+        raw.put(b"bad", b"\xff").unwrap();
+    }
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    db.put("a", "1").unwrap();
+    assert_eq!(db.get_or("a", "d".into()).unwrap(), "1");
+    assert_eq!(db.get_or("b", "d".into()).unwrap(), "d");
+    assert_eq!(db.get_or_else("b", || "e".into()).unwrap(), "e");
+    assert!(db.get_or("bad", "d".into()).is_err());
+    assert!(db.get_or_else("bad", || unreachable!()).is_err());
+}
+
+#[test]
+fn delete_if_checks_the_value() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, u64, u64> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.put("a", 3).unwrap();
+    assert!(!db.delete_if("a", |v| *v == 4).unwrap());
+    assert_eq!(db.get("a").unwrap(), Some(3));
+    assert!(db.delete_if("a", |v| *v == 3).unwrap());
+    assert_eq!(db.get("a").unwrap(), None);
+    assert!(!db.delete_if("a", |_| true).unwrap());
+}
+
+#[test]
+fn get_raw_deserializes_on_demand() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.put("k", "hello").unwrap();
+    let raw = db.get_raw("k").unwrap().unwrap();
+    assert_eq!(&*raw, b"hello");
+    assert_eq!(raw.deserialize::<String>().unwrap(), "hello");
+    assert_eq!(raw.deserialize_borrowed::<&str>().unwrap(), "hello");
+    assert!(raw.deserialize::<u64>().is_err());
+    assert!(db.get_raw("x").unwrap().is_none());
+}
+
+#[test]
+fn multi_get_pairs_keeps_keys_with_values() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, u64, u64> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.put("a", 1).unwrap();
+    db.put("c", 3).unwrap();
+    let got = db.multi_get_pairs(vec!["c", "b", "a", "c"]).unwrap();
+    assert_eq!(
+        got,
+        vec![("c", Some(3)), ("b", None), ("a", Some(1)), ("c", Some(3))]
+    );
+}
+
+#[test]
+fn put_if_changed_skips_identical_values() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_with_stats(dir.path().join("db")).unwrap();
+    assert!(db.put_if_changed("k", "a").unwrap());
+    for _ in 0..10 {
+        assert!(!db.put_if_changed("k", "a").unwrap());
+    }
+    assert!(db.put_if_changed("k", "b").unwrap());
+    assert!(db
+        .statistics()
+        .unwrap()
+        .contains("rocksdb.number.keys.written COUNT : 2\n"));
+    assert_eq!(db.get("k").unwrap().as_deref(), Some("b"));
+}
+
+#[test]
+fn entries_collects_or_fails() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    for i in 0..20 {
+        db.put(i, &i.to_string()).unwrap();
+    }
+    let e = db.entries().unwrap();
+    assert_eq!(e.len(), 20);
+    assert_eq!(e[7], (7, "7".to_string()));
+    drop(db);
+    let raw: KeyValueDB<&[u8], Vec<u8>, &[u8]> = KeyValueDB::open_default(&p).unwrap();
+    raw.put(&10u32.to_be_bytes()[..], &[0xff][..]).unwrap();
+    drop(raw);
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert!(matches!(
+        db.entries(),
+        Err(RustyRocksError::ValueDeserialize { .. })
+    ));
+}
+
+#[test]
+fn delete_prefix() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&[u8], String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for k in [
+        &b"aa1"[..],
+        b"aa2",
+        b"ab",
+        b"a",
+        b"b",
+        b"\xff\xff",
+        b"\xff\xff\x01",
+        b"\xff",
+    ] {
+        db.put(k, "v").unwrap();
+    }
+    db.delete_prefix(b"aa").unwrap();
+    let keys: Vec<Vec<u8>> = db.db_keys().map(Result::unwrap).collect();
+    assert_eq!(
+        keys,
+        vec![
+            b"a".to_vec(),
+            b"ab".to_vec(),
+            b"b".to_vec(),
+            b"\xff".to_vec(),
+            b"\xff\xff".to_vec(),
+            b"\xff\xff\x01".to_vec()
+        ]
+    );
+    db.delete_prefix(b"\xff\xff").unwrap();
+    let keys: Vec<Vec<u8>> = db.db_keys().map(Result::unwrap).collect();
+    assert_eq!(
+        keys,
+        vec![
+            b"a".to_vec(),
+            b"ab".to_vec(),
+            b"b".to_vec(),
+            b"\xff".to_vec()
+        ]
+    );
+    db.delete_prefix(b"\xff\xff").unwrap();
+}
+
+#[test]
+fn write_raw_applies_a_batch() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    let mut b = rocksdb::WriteBatch::default();
+    b.put(b"a", b"one");
+    b.put(b"b", b"two");
+    db.write_raw(b).unwrap();
+    assert_eq!(db.get("a").unwrap().as_deref(), Some("one"));
+    assert_eq!(db.get("b").unwrap().as_deref(), Some("two"));
+}
+
+#[test]
+fn take_removes_and_returns() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.put("a", "one").unwrap();
+    assert_eq!(db.take("a").unwrap().as_deref(), Some("one"));
+    assert_eq!(db.get("a").unwrap(), None);
+    assert_eq!(db.take("a").unwrap(), None);
+    let t: TransactionalDB<&str, String, &str> =
+        TransactionalDB::open_default(dir.path().join("txn")).unwrap();
+    let txn = t.transaction();
+    txn.put("a", "one").unwrap();
+    txn.commit().unwrap();
+    assert_eq!(t.take("a").unwrap().as_deref(), Some("one"));
+    assert_eq!(t.get("a").unwrap(), None);
+    assert_eq!(t.take("a").unwrap(), None);
+}
+
+#[test]
+fn clones_share_the_database_across_threads() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, u32, u32> = KeyValueDB::open_with_stats(dir.path().join("db")).unwrap();
+    let handles: Vec<_> = (0..4u32)
+        .map(|t| {
+            let db = db.clone();
+            std::thread::spawn(move || {
+                for i in 0..100u32 {
+                    db.put(t * 1000 + i, i).unwrap();
+                    assert_eq!(db.get(t * 1000 + i).unwrap(), Some(i));
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    assert_eq!(db.db_iter().count(), 400);
+    assert!(db.clone().statistics().is_some());
+}
+
+#[test]
+fn put_sorted_requires_sorted_keys() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, u32, u32> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.put_sorted((0..100u32).map(|i| (i, i))).unwrap();
+    db.put_sorted(vec![(200, 1), (200, 2)]).unwrap();
+    assert_eq!(db.get(200).unwrap(), Some(2));
+    assert_eq!(db.count().unwrap(), 101);
+    match db.checked_put_sorted(vec![(300, 1), (301, 1), (299, 1)]) {
+        Err(RustyRocksError::KeysOutOfOrder { index: 2 }) => {}
+        r => panic!("{:?}", r),
+    }
+    assert_eq!(db.get(300).unwrap(), None);
+    let r = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        db.put_sorted(vec![(5, 1), (4, 1)])
+    }));
+    assert!(r.is_err());
+}
+
+#[test]
+fn extend_and_try_extend() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, u32, u32> = KeyValueDB::open_default(&p).unwrap();
+    let mut h = &db;
+    h.extend((0..10u32).rev().map(|i| (i, i * 3)));
+    db.try_extend(vec![(20, 1), (15, 2)]).unwrap();
+    assert_eq!(db.get(7).unwrap(), Some(21));
+    assert_eq!(db.count().unwrap(), 12);
+    let ro: KeyValueDB<u32, u32, u32> = KeyValueDB::open_read_only(&p, false).unwrap();
+    assert!(matches!(
+        ro.try_extend(vec![(1, 1)]),
+        Err(RustyRocksError::ReadOnly)
+    ));
+}
+
+#[test]
+fn multi_get_or_fills_in_defaults() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, u32, u32> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.put(1, 10).unwrap();
+    db.put(3, 30).unwrap();
+    assert_eq!(
+        db.multi_get_or(vec![0, 1, 2, 3, 1], 99).unwrap(),
+        vec![99, 10, 99, 30, 10]
+    );
+    assert!(db.multi_get_or(Vec::new(), 0).unwrap().is_empty());
+}
+
+#[test]
+fn key_may_exist_has_no_false_negatives() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    assert!(!db.key_may_exist("a").unwrap());
+    db.put("a", "1").unwrap();
+    assert!(db.key_may_exist("a").unwrap());
+    db.flush().unwrap();
+    assert!(db.key_may_exist("a").unwrap());
+    db.delete("a").unwrap();
+    assert!(!db.key_may_exist("a").unwrap());
+}
+
+#[test]
+fn close_flushes_and_releases() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    db.put("a", "1").unwrap();
+    let clone = db.clone();
+    db.close().unwrap();
+    assert_eq!(clone.get("a").unwrap().as_deref(), Some("1"));
+    clone.close().unwrap();
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert_eq!(db.get("a").unwrap().as_deref(), Some("1"));
+    drop(db);
+    let ro: KeyValueDB<&str, String, &str> = KeyValueDB::open_read_only(&p, false).unwrap();
+    ro.close().unwrap();
+    let m: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("merge")).unwrap();
+    m.merge("k", "x").unwrap();
+    m.close().unwrap();
+}
+
+#[test]
+fn try_get_separates_read_and_decode_errors() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        db.put("ok", "fine").unwrap();
+    }
+    {
+        let db: KeyValueDB<&str, Vec<u8>, &[u8]> = KeyValueDB::open_default(&p).unwrap();
+        db.put("bad", &[0xff, 0xfe][..]).unwrap();
+    }
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert!(db.try_get("none").unwrap().is_none());
+    assert_eq!(db.try_get("ok").unwrap().unwrap().unwrap(), "fine");
+    assert!(db.try_get("bad").unwrap().unwrap().is_err());
+}
+
+#[test]
+fn value_size_does_not_deserialize() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, Boom, &str> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.put("a", "hello").unwrap();
+    db.put("e", "").unwrap();
+    assert_eq!(db.value_size("a").unwrap(), Some(5));
+    assert_eq!(db.value_size("e").unwrap(), Some(0));
+    assert_eq!(db.value_size("z").unwrap(), None);
+}
+
+#[test]
+fn get_memtable_only_skips_the_disk() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        db.put("k", "v").unwrap();
+        assert_eq!(db.get_memtable_only("k").unwrap().unwrap(), "v");
+        assert!(db.get_memtable_only("none").unwrap().is_none());
+    }
+    // Reopening flushes the memtable and starts with an empty block cache.
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert!(db.get_memtable_only("k").unwrap().is_none());
+    assert_eq!(db.get("k").unwrap().unwrap(), "v");
+    assert_eq!(db.get_memtable_only("k").unwrap().unwrap(), "v");
+}
@@ -0,0 +1,544 @@
+use rustyrocks::*;
+use tempfile::tempdir;
+
+fn count_sst(p: &std::path::Path) -> usize {
+    std::fs::read_dir(p)
+        .unwrap()
+        .filter(|e| {
+            e.as_ref()
+                .unwrap()
+                .path()
+                .extension()
+                .is_some_and(|x| x == "sst")
+        })
+        .count()
+}
+
+#[test]
+fn flush_writes_an_sst_file() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    db.put(1, "a").unwrap();
+    assert_eq!(count_sst(&p), 0);
+    db.flush().unwrap();
+    db.flush_wal(true).unwrap();
+    assert_eq!(count_sst(&p), 1);
+}
+
+#[test]
+fn approximate_len_and_is_empty() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert!(db.is_empty().unwrap());
+    for k in 0..1000u32 {
+        db.put(k, "a").unwrap();
+    }
+    db.flush().unwrap();
+    let n = db.approximate_len().unwrap();
+    assert!((900..=1100).contains(&n), "{}", n);
+    assert!(!db.is_empty().unwrap());
+}
+
+#[test]
+fn compaction_drops_deleted_keys() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    let big = "x".repeat(1000);
+    for k in 0..2000u32 {
+        db.put(k, &big).unwrap();
+    }
+    db.flush().unwrap();
+    let raw = rocksdb::DB::list_cf(&rocksdb::Options::default(), &p).unwrap();
+    assert!(!raw.is_empty());
+    db.delete_range(0, 2000).unwrap();
+    db.compact_range(Some(0), None).unwrap();
+    db.compact_all();
+    assert!(db.is_empty().unwrap());
+}
+
+#[test]
+fn destroy_removes_the_database() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    type Db<'a> = KeyValueDB<&'a str, String, &'a str>;
+    let db: Db = KeyValueDB::open_default(&p).unwrap();
+    db.put("a", "b").unwrap();
+    let path = db.path().to_path_buf();
+    assert_eq!(path, p);
+    drop(db);
+    Db::destroy(&path, &rocksdb::Options::default()).unwrap();
+    assert!(!p.exists());
+}
+
+#[test]
+fn backup_purge_and_restore() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let b = dir.path().join("backups");
+    type Db<'a> = KeyValueDB<&'a str, String, &'a str>;
+    {
+        let db: Db = KeyValueDB::open_default(&p).unwrap();
+        db.put("a", "1").unwrap();
+        db.backup(&b).unwrap();
+        db.put("a", "2").unwrap();
+        db.backup(&b).unwrap();
+        db.backup(&b).unwrap();
+    }
+    Db::purge_old_backups(&b, 2).unwrap();
+    Db::destroy(&p, &rocksdb::Options::default()).unwrap();
+    Db::restore_from_backup(&b, &p).unwrap();
+    let db: Db = KeyValueDB::open_default(&p).unwrap();
+    assert_eq!(db.get("a").unwrap().unwrap(), "2");
+    let n = std::fs::read_dir(b.join("meta")).unwrap().count();
+    assert_eq!(n, 2);
+}
+
+#[test]
+fn property_values() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    for k in 0..10u32 {
+        db.put(k, "v").unwrap();
+    }
+    assert_eq!(
+        db.property_int_value(properties::ESTIMATE_NUM_KEYS)
+            .unwrap(),
+        Some(10)
+    );
+    assert!(db
+        .property_value(properties::STATS)
+        .unwrap()
+        .unwrap()
+        .contains("Compaction"));
+    assert_eq!(db.property_value("rocksdb.nope").unwrap(), None);
+}
+
+#[test]
+fn custom_comparator_orders_keys() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    fn num(a: &[u8], b: &[u8]) -> std::cmp::Ordering {
+        let p = |x: &[u8]| std::str::from_utf8(x).unwrap().parse::<u64>().unwrap();
+        p(a).cmp(&p(b))
+    }
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_with_comparator(&p, "numeric", num).unwrap();
+    for k in ["10", "9", "100", "2"] {
+        db.put(k, "v").unwrap();
+    }
+    let ks: Vec<String> = db.db_keys().collect::<Result<_, _>>().unwrap();
+    assert_eq!(ks, vec!["2", "9", "10", "100"]);
+    drop(db);
+    assert!(KeyValueDB::<&str, String, &str>::open_default(&p).is_err());
+}
+
+#[test]
+fn databases_can_share_a_block_cache() {
+    let dir = tempdir().unwrap();
+    let cache = rocksdb::Cache::new_lru_cache(1 << 20).unwrap();
+    let a: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_with_cache(dir.path().join("a"), &cache).unwrap();
+    let b: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_with_cache(dir.path().join("b"), &cache.clone()).unwrap();
+    for k in 0..100u32 {
+        a.put(k, "a").unwrap();
+        b.put(k, "b").unwrap();
+    }
+    a.flush().unwrap();
+    b.flush().unwrap();
+    assert_eq!(a.get(5).unwrap().unwrap(), "a");
+    assert_eq!(b.get(5).unwrap().unwrap(), "b");
+    assert!(cache.get_usage() > 0);
+}
+
+#[test]
+fn compaction_filter_removes_values() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_with_compaction_filter(dir.path().join("db"), "drop-dead", |_k, v| {
+            if v == b"dead" {
+                FilterDecision::Remove
+            } else {
+                FilterDecision::Keep
+            }
+        })
+        .unwrap();
+    for k in 0..10u32 {
+        db.put(k, if k % 2 == 0 { "dead" } else { "alive" })
+            .unwrap();
+    }
+    db.flush().unwrap();
+    db.compact_all();
+    let ks: Vec<u32> = db.db_keys().collect::<Result<_, _>>().unwrap();
+    assert_eq!(ks, vec![1, 3, 5, 7, 9]);
+}
+
+#[test]
+fn secondary_instance_catches_up() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let s = dir.path().join("secondary");
+    let prim: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    prim.put("a", "1").unwrap();
+    let sec: KeyValueDB<&str, String, &str> = KeyValueDB::open_secondary(&p, &s).unwrap();
+    assert_eq!(sec.get("a").unwrap().as_deref(), Some("1"));
+    prim.put("b", "2").unwrap();
+    sec.try_catch_up_with_primary().unwrap();
+    assert_eq!(sec.get("b").unwrap().as_deref(), Some("2"));
+    assert!(matches!(sec.put("c", "3"), Err(RustyRocksError::ReadOnly)));
+}
+
+#[test]
+fn checkpoint_is_a_point_in_time_copy() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let c = dir.path().join("checkpoint");
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    db.put("a", "1").unwrap();
+    db.create_checkpoint(&c).unwrap();
+    db.put("b", "2").unwrap();
+    let cp: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&c).unwrap();
+    assert_eq!(cp.get("a").unwrap().as_deref(), Some("1"));
+    assert_eq!(cp.get("b").unwrap(), None);
+}
+
+#[test]
+fn statistics_count_reads_and_writes() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_with_stats(dir.path().join("db")).unwrap();
+    for i in 0..100 {
+        db.put(&format!("k{}", i), "v").unwrap();
+        db.get(&format!("k{}", i)).unwrap();
+    }
+    let s = db.statistics().unwrap();
+    assert!(
+        s.contains("rocksdb.number.keys.written COUNT : 100"),
+        "{}",
+        s
+    );
+    assert!(s.contains("rocksdb.number.keys.read"));
+    let plain: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("plain")).unwrap();
+    assert!(plain.statistics().is_none());
+}
+
+#[test]
+fn live_files_describe_sst_files() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    assert!(db.live_files().unwrap().is_empty());
+    for i in 10..20 {
+        db.put(i, "v").unwrap();
+    }
+    db.flush().unwrap();
+    let files = db.live_files().unwrap();
+    assert_eq!(files.len(), 1);
+    let f = &files[0];
+    assert_eq!((f.smallest_key, f.largest_key), (Some(10), Some(19)));
+    assert_eq!(
+        (f.level, f.num_entries, f.column_family.as_str()),
+        (0, 10, "default")
+    );
+    assert!(f.size > 0 && f.name.ends_with(".sst"));
+}
+
+#[test]
+fn secondary_follows_the_primary() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let s = dir.path().join("secondary");
+    let prim: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    prim.put("a", "1").unwrap();
+    let sec: KeyValueDB<&str, String, &str> = KeyValueDB::open_secondary(&p, &s).unwrap();
+    let writer = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        prim.put("b", "2").unwrap();
+        prim
+    });
+    let start = std::time::Instant::now();
+    let mut seen = None;
+    sec.follow(std::time::Duration::from_millis(10), || {
+        seen = sec.get("b").unwrap();
+        seen.is_none() && start.elapsed().as_secs() < 5
+    })
+    .unwrap();
+    assert_eq!(seen.as_deref(), Some("2"));
+    drop(writer.join().unwrap());
+}
+
+#[test]
+fn size_in_range_grows_with_data() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u32, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    let v = "x".repeat(100);
+    for i in 0..1000u32 {
+        db.put(i, v.as_str()).unwrap();
+    }
+    db.flush().unwrap();
+    let small = db.size_in_range(0, 1000).unwrap();
+    assert!(small > 0);
+    for i in 1000..5000u32 {
+        db.put(i, v.as_str()).unwrap();
+    }
+    db.flush().unwrap();
+    let big = db.size_in_range(0, 5000).unwrap();
+    assert!(big > small * 2, "{} {}", big, small);
+    assert_eq!(db.size_in_range(10000, 20000).unwrap(), 0);
+}
+
+#[test]
+fn stats_snapshot_parses_counters_and_histograms() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_with_stats(dir.path().join("db")).unwrap();
+    for i in 0..10 {
+        db.put(&format!("k{}", i), "v").unwrap();
+    }
+    db.get("k1").unwrap();
+    let s = db.stats_snapshot();
+    assert!(
+        s["rocksdb.number.keys.written"] >= 10.0,
+        "{:?}",
+        s.get("rocksdb.number.keys.written")
+    );
+    assert!(s.contains_key("rocksdb.db.get.micros.p50"));
+    assert!(s["rocksdb.db.get.micros.count"] >= 1.0);
+    let plain: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("plain")).unwrap();
+    assert!(plain.stats_snapshot().is_empty());
+}
+
+#[test]
+fn verify_checksums_detects_corruption() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        let v = "abcdefgh".repeat(50);
+        for i in 0..2000u32 {
+            db.put(i, v.as_str()).unwrap();
+        }
+        db.flush().unwrap();
+        db.verify_checksums().unwrap();
+    }
+    let sst = std::fs::read_dir(&p)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == "sst"))
+        .unwrap();
+    let mut bytes = std::fs::read(&sst).unwrap();
+    let mid = bytes.len() / 3;
+    for b in &mut bytes[mid..mid + 16] {
+        *b ^= 0xff;
+    }
+    std::fs::write(&sst, bytes).unwrap();
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    match db.verify_checksums() {
+        Err(e @ RustyRocksError::Corruption(_)) => {
+            assert!(std::error::Error::source(&e).is_some());
+        }
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn custom_env() {
+    let dir = tempdir().unwrap();
+    let mem = rocksdb::Env::mem_env().unwrap();
+    let p = dir.path().join("mem");
+    {
+        let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_with_env(&p, &mem).unwrap();
+        db.put("a", "1").unwrap();
+        db.flush().unwrap();
+        assert_eq!(db.get("a").unwrap().as_deref(), Some("1"));
+    }
+    assert!(!p.exists() || std::fs::read_dir(&p).map(|d| d.count()).unwrap_or(0) == 0);
+    let mut env = rocksdb::Env::default().unwrap();
+    env.set_background_threads(1);
+    env.set_low_priority_background_threads(1);
+    env.lower_thread_pool_io_priority();
+    let db: KeyValueDB<u32, u32, u32> =
+        KeyValueDB::open_with_env(dir.path().join("db"), &env).unwrap();
+    drop(env);
+    for i in 0..1000 {
+        db.put(i, i).unwrap();
+    }
+    db.flush().unwrap();
+    db.compact_all();
+    assert_eq!(db.get(999).unwrap(), Some(999));
+}
+
+#[test]
+fn cancel_background_work_keeps_data() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        let v = "y".repeat(1000);
+        for round in 0..5u32 {
+            for i in 0..2000u32 {
+                db.put(round * 10000 + i, v.as_str()).unwrap();
+            }
+            db.flush().unwrap();
+        }
+        let t = std::time::Instant::now();
+        db.cancel_background_work(true);
+        assert!(t.elapsed() < std::time::Duration::from_secs(10));
+        db.put(1, "after").unwrap();
+    }
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert_eq!(db.get(1).unwrap().as_deref(), Some("after"));
+    assert_eq!(db.count().unwrap(), 10000);
+}
+
+#[test]
+fn sync_persists_to_sst_files() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        db.put("a", "1").unwrap();
+        db.sync().unwrap();
+        assert!(!db.live_files().unwrap().is_empty());
+    }
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert_eq!(db.get("a").unwrap().as_deref(), Some("1"));
+}
+
+#[test]
+fn disable_and_enable_auto_compactions() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u64, u64, u64> = KeyValueDB::open_cf(dir.path().join("db"), ["x"]).unwrap();
+    db.disable_auto_compactions().unwrap();
+    assert_eq!(
+        db.property_int_value("rocksdb.num-running-compactions")
+            .unwrap(),
+        Some(0)
+    );
+    for round in 0..8u64 {
+        for i in 0..200u64 {
+            db.put(i, round * 1000 + i).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    let l0 = db
+        .live_files()
+        .unwrap()
+        .iter()
+        .filter(|f| f.level == 0)
+        .count();
+    assert!(l0 >= 8, "{}", l0);
+    db.enable_auto_compactions().unwrap();
+    db.compact_all();
+    assert!(db
+        .live_files()
+        .unwrap()
+        .iter()
+        .all(|f| f.level > 0 || f.column_family != "default"));
+    for i in 0..200u64 {
+        assert_eq!(db.get(i).unwrap(), Some(7000 + i));
+    }
+}
+
+#[test]
+fn repair_keeps_data() {
+    let dir = tempdir().unwrap();
+    type Db = KeyValueDB<&'static str, String, &'static str>;
+    let p = dir.path().join("db");
+    {
+        let db: Db = KeyValueDB::open_default(&p).unwrap();
+        db.put("a", "1").unwrap();
+        db.flush().unwrap();
+        db.put("b", "2").unwrap();
+    }
+    Db::repair(&p, &rocksdb::Options::default()).unwrap();
+    let db: Db = KeyValueDB::open_default(&p).unwrap();
+    assert_eq!(db.get("a").unwrap().as_deref(), Some("1"));
+    assert_eq!(db.get("b").unwrap().as_deref(), Some("2"));
+}
+
+#[test]
+fn range_count_estimates_track_exact_counts() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u64, u64, u64> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    for chunk in 0..10u64 {
+        db.try_extend((chunk * 1000..(chunk + 1) * 1000).map(|i| (i * 7, i)))
+            .unwrap();
+        db.flush().unwrap();
+    }
+    for (s, e) in [
+        (0u64, 70_000u64),
+        (14_000, 35_000),
+        (100, 200),
+        (20_000, 20_700),
+        (69_000, 1 << 40),
+        (1 << 40, 1 << 41),
+    ] {
+        let exact = db.range_count(s, e, true).unwrap();
+        let approx = db.range_count(s, e, false).unwrap();
+        let expect = (0..10_000u64).filter(|i| (s..e).contains(&(i * 7))).count() as u64;
+        assert_eq!(exact, expect);
+        let tol = (expect / 10).max(3);
+        assert!(
+            (approx as i64 - exact as i64).unsigned_abs() <= tol,
+            "{}..{}: {} vs {}",
+            s,
+            e,
+            approx,
+            exact
+        );
+    }
+    db.compact_all();
+    assert!((db.range_count(14_000, 35_000, false).unwrap() as i64 - 3000).abs() < 300);
+}
+
+#[test]
+fn range_count_estimates_include_memtables() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u64, u64, u64> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.try_extend((0..5_000u64).map(|i| (i * 3, i))).unwrap();
+    let scanned = || {
+        db.db_iter_from(3_000, rocksdb::Direction::Forward)
+            .unwrap()
+            .map(Result::unwrap)
+            .take_while(|&(k, _)| k < 9_000)
+            .count() as u64
+    };
+    assert_eq!(scanned(), 2_000);
+    for flushed in [false, true] {
+        if flushed {
+            db.flush().unwrap();
+        }
+        let approx = db.range_count(3_000, 9_000, false).unwrap();
+        assert!(
+            (approx as i64 - scanned() as i64).unsigned_abs() <= 200,
+            "flushed: {}, {} vs {}",
+            flushed,
+            approx,
+            scanned()
+        );
+    }
+    assert_eq!(db.range_count(20_000, 30_000, false).unwrap(), 0);
+}
+
+#[test]
+fn exact_range_counts_cross_prefixes() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, u64, u64> =
+        KeyValueDB::open_with_prefix(dir.path().join("db"), 2).unwrap();
+    // Two files, the second with no key under the prefix "aa" of `start`.
+    for keys in [&["aa1", "aa2"][..], &["ab1", "ac1", "ac2", "ad1"]] {
+        for &key in keys {
+            db.put(key, 1).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    assert_eq!(db.range_count("aa2", "ad", true).unwrap(), 4);
+}
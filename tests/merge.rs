@@ -0,0 +1,633 @@
+mod common;
+
+use common::{Last, Words};
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[test]
+fn skip_policy_drops_corrupt_operands() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("db")).unwrap();
+    db.put("k", "a").unwrap();
+    db.merge("k", "b").unwrap();
+    db.merge("k", "!s").unwrap();
+    db.merge("k", "c").unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap().0, vec!["a", "b", "c"]);
+    db.merge("fresh", "!s").unwrap();
+    db.merge("fresh", "d").unwrap();
+    assert_eq!(db.get("fresh").unwrap().unwrap().0, vec!["d"]);
+}
+
+#[test]
+fn use_policy_replaces_corrupt_operands() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("db")).unwrap();
+    db.merge("u", "x").unwrap();
+    db.merge("u", "!u").unwrap();
+    assert_eq!(db.get("u").unwrap().unwrap().0, vec!["x", "used"]);
+    db.merge("u", "y").unwrap();
+    assert_eq!(db.get("u").unwrap().unwrap().0, vec!["x", "used", "y"]);
+}
+
+#[test]
+fn abort_policy_leaves_the_existing_value() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("db")).unwrap();
+    db.put("a", "x").unwrap();
+    db.merge("a", "y").unwrap();
+    db.merge("a", "!a").unwrap();
+    assert_eq!(db.get("a").unwrap().unwrap().0, vec!["x"]);
+}
+
+#[test]
+fn error_handler_sees_corrupt_operands() {
+    use std::sync::{Arc, Mutex};
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(&p).unwrap();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let s2 = seen.clone();
+    db.set_error_handler(move |e| {
+        s2.lock()
+            .unwrap()
+            .push((e.key.to_vec(), e.bytes.to_vec(), e.error.to_string()))
+    });
+    db.merge("k", "a").unwrap();
+    db.merge("k", "!s").unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap().0, vec!["a"]);
+    let v = seen.lock().unwrap();
+    assert!(!v.is_empty());
+    assert_eq!(v[0].0, b"k");
+    assert_eq!(v[0].1, b"!s");
+}
+
+#[test]
+fn merge_owned_values() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("db")).unwrap();
+    db.merge_owned("k", Words(vec!["a".into()])).unwrap();
+    db.merge_owned("k", Words(vec!["b".into(), "c".into()]))
+        .unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap().0, vec!["a", "b", "c"]);
+}
+
+#[derive(Debug)]
+struct Sum(u64);
+static PARTIALS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+impl Deserializable for Sum {
+    type Error = LengthError;
+    fn deserialize(b: &[u8]) -> Result<Self, LengthError> {
+        u64::deserialize(b).map(Sum)
+    }
+}
+impl AssociateMergeable for Sum {
+    fn merge(&mut self, o: Self) {
+        self.0 += o.0
+    }
+    fn partial_merge(&mut self, o: Self) {
+        PARTIALS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.0 += o.0
+    }
+    fn into_bytes(self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+    fn handle_deser_error(_k: &[u8], _b: &[u8], _e: LengthError) -> MergePolicy<Self> {
+        MergePolicy::Abort
+    }
+}
+
+#[test]
+fn partial_merges_run_during_flushes() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: MergeableDB<u32, Sum, u64> = MergeableDB::new(&p).unwrap();
+    for i in 0..100u64 {
+        db.merge(1, i).unwrap();
+        if i % 10 == 0 {
+            db.flush().unwrap();
+        }
+    }
+    db.flush().unwrap();
+    assert_eq!(db.get(1).unwrap().unwrap().0, 4950);
+    assert!(PARTIALS.load(std::sync::atomic::Ordering::SeqCst) > 0);
+}
+
+#[test]
+fn merge_batch_matches_individual_merges() {
+    let dir = tempdir().unwrap();
+    let a: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("a")).unwrap();
+    let b: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("b")).unwrap();
+    let keys = ["x", "y", "z"];
+    let vals = ["1", "2", "3", "4", "5", "6", "7"];
+    let ops: Vec<(&str, &str)> = vals
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (keys[i % 3], *v))
+        .collect();
+    for (k, v) in &ops {
+        a.merge(k, v).unwrap();
+    }
+    b.merge_batch(ops.iter().copied()).unwrap();
+    for k in keys {
+        assert_eq!(a.get(k).unwrap(), b.get(k).unwrap());
+    }
+    assert_eq!(b.get("x").unwrap().unwrap().0, vec!["1", "4", "7"]);
+}
+
+#[test]
+fn merged_values_can_be_read_in_place() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: MergeableDB<&str, Words, &[u8]> = MergeableDB::new(&p).unwrap();
+    db.merge("k", b"a").unwrap();
+    db.merge("k", b"b").unwrap();
+    let n = db
+        .with_value("k", |b| b.split(|&c| c == b',').count())
+        .unwrap();
+    assert_eq!(n, Some(2));
+    let s = db.get_borrowed::<str, _, _>("k", |s| s.len()).unwrap();
+    assert_eq!(s, Some(3));
+}
+
+#[test]
+fn builder_create_and_exists_flags() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let r = MergeableDB::<&str, Words, &str>::builder()
+        .create_if_missing(false)
+        .open(&p);
+    assert!(r.is_err());
+    drop(MergeableDB::<&str, Words, &str>::new(&p).unwrap());
+    let r = MergeableDB::<&str, Words, &str>::builder()
+        .error_if_exists(true)
+        .open(&p);
+    assert!(r.is_err());
+    let db = MergeableDB::<&str, Words, &str>::builder()
+        .create_if_missing(false)
+        .open(&p)
+        .unwrap();
+    db.merge("a", "x").unwrap();
+}
+
+#[test]
+fn concurrent_updates_are_not_lost() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(&p).unwrap();
+    std::thread::scope(|s| {
+        for t in 0..8 {
+            let db = &db;
+            s.spawn(move || {
+                for i in 0..20 {
+                    db.update("k", |cur| {
+                        let _ = cur.map(|w| w.0.len());
+                        Words(vec![format!("{}-{}", t, i)])
+                    })
+                    .unwrap();
+                }
+            });
+        }
+    });
+    let mut all = db.get("k").unwrap().unwrap().0;
+    all.sort();
+    all.dedup();
+    assert_eq!(all.len(), 160);
+}
+
+#[test]
+fn wal_options_keep_data() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db = MergeableDB::<&str, Words, &str>::builder()
+            .max_total_wal_size(64 * 1024)
+            .recycle_log_file_num(2)
+            .keep_log_file_num(3)
+            .open(&p)
+            .unwrap();
+        for i in 0..5000 {
+            db.merge(&format!("k{}", i % 100), &format!("v{}", i))
+                .unwrap();
+        }
+    }
+    let db = MergeableDB::<&str, Words, &str>::new(&p).unwrap();
+    assert_eq!(db.get("k7").unwrap().unwrap().0.len(), 50);
+}
+
+#[test]
+fn extend_merge_merges_in_order() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("db")).unwrap();
+    let keys = ["a", "b", "c"];
+    let vals: Vec<String> = (0..3000).map(|i| format!("v{}", i)).collect();
+    db.extend_merge(
+        vals.iter()
+            .enumerate()
+            .map(|(i, v)| (keys[i % 3], v.as_str())),
+    )
+    .unwrap();
+    assert_eq!(db.get("a").unwrap().unwrap().0.len(), 1000);
+    assert_eq!(db.get("c").unwrap().unwrap().0[0], "v2");
+}
+
+#[test]
+fn resource_limits_are_accepted() {
+    let dir = tempdir().unwrap();
+    let db = MergeableDB::<&str, Words, &str>::builder()
+        .max_open_files(64)
+        .max_background_jobs(2)
+        .max_write_buffer_number(3)
+        .open(dir.path().join("db"))
+        .unwrap();
+    for i in 0..50 {
+        db.merge("k", &format!("{}", i)).unwrap();
+        db.flush().unwrap();
+    }
+    assert_eq!(db.get("k").unwrap().unwrap().0.len(), 50);
+}
+
+#[test]
+fn db_write_buffer_size_flushes_early() {
+    let dir = tempdir().unwrap();
+    let open = |name: &str| {
+        MergeableDB::<&str, Words, &str>::builder()
+            .db_write_buffer_size(32 << 10)
+            .open(dir.path().join(name))
+            .unwrap()
+    };
+    let (a, b) = (open("a"), open("b"));
+    let value = "x".repeat(1000);
+    for i in 0..500 {
+        let key = format!("{:05}", i);
+        a.merge(&key, &value).unwrap();
+        b.merge(&key, &value).unwrap();
+    }
+    for (db, name) in [(&a, "a"), (&b, "b")] {
+        assert_eq!(db.get("00499").unwrap().unwrap().0, vec![value.clone()]);
+        let ssts = std::fs::read_dir(dir.path().join(name))
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension() == Some("sst".as_ref()))
+            .count();
+        assert!(ssts > 0, "{} has not been flushed", name);
+    }
+}
+
+#[test]
+fn reducer_replaces_merge() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> =
+        MergeableDB::with_reducer(dir.path().join("db"), |a: &mut Words, b: Words| {
+            // keep the lexicographically largest word
+            let m = a.0.iter().chain(&b.0).max().cloned();
+            a.0 = m.into_iter().collect();
+        })
+        .unwrap();
+    for w in ["pear", "apple", "zebra", "mango"] {
+        db.merge("k", w).unwrap();
+    }
+    assert_eq!(db.get("k").unwrap().unwrap().0, vec!["zebra".to_string()]);
+    db.flush().unwrap();
+    db.merge("k", "zzz").unwrap();
+    db.merge("k", "a").unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap().0, vec!["zzz".to_string()]);
+}
+
+#[test]
+fn reopening_checks_the_merge_operator_name() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: MergeableDB<&str, Words, &str> = MergeableDB::with_name(&p, "custom op").unwrap();
+        db.merge("k", "a").unwrap();
+    }
+    {
+        let db: MergeableDB<&str, Words, &str> = MergeableDB::with_name(&p, "custom op").unwrap();
+        db.merge("k", "b").unwrap();
+        assert_eq!(db.get("k").unwrap().unwrap().0, vec!["a", "b"]);
+    }
+    let r = MergeableDB::<&str, Words, &str>::new(&p);
+    match r {
+        Err(RustyRocksError::MergeOperatorMismatch { expected, found }) => {
+            assert_eq!(found, "custom op");
+            assert_eq!(expected, "rustyrocks merge");
+        }
+        Err(e) => panic!("{}", e),
+        Ok(_) => panic!("opened"),
+    }
+    let p2 = dir.path().join("plain");
+    drop(KeyValueDB::<&str, String, &str>::open_default(&p2).unwrap());
+    drop(MergeableDB::<&str, Words, &str>::new(&p2).unwrap());
+    drop(MergeableDB::<&str, Words, &str>::new(&p2).unwrap());
+}
+
+#[test]
+fn read_only_opens_use_the_recorded_operator_name() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: MergeableDB<&str, Words, &str> = MergeableDB::with_name(&p, "custom op").unwrap();
+        db.merge("k", "a").unwrap();
+        db.flush().unwrap();
+        db.merge("k", "b").unwrap();
+    }
+    let ro = MergeableDB::<&str, Words, &str>::open_read_only(&p, false).unwrap();
+    assert_eq!(ro.get("k").unwrap().unwrap().0, vec!["a", "b"]);
+    drop(ro);
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::with_name(&p, "custom op").unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap().0, vec!["a", "b"]);
+}
+
+#[test]
+fn compact_on_open_reclaims_space() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let big = "x".repeat(1000);
+    {
+        let db = MergeableDB::<&str, Words, &str>::new(&p).unwrap();
+        for i in 0..2000 {
+            db.put(&format!("k{}", i), &big).unwrap();
+        }
+        db.flush().unwrap();
+        for i in 0..2000 {
+            db.delete(&format!("k{}", i)).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    let size = |db: &KeyValueDB<&str, String, &str>| {
+        db.property_int_value(rustyrocks::properties::TOTAL_SST_FILES_SIZE)
+            .unwrap()
+            .unwrap()
+    };
+    let before = {
+        let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        size(&db)
+    };
+    drop(
+        MergeableDB::<&str, Words, &str>::builder()
+            .compact_on_open(true)
+            .open(&p)
+            .unwrap(),
+    );
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    let after = size(&db);
+    assert!(after < before / 10, "{} {}", before, after);
+}
+
+#[test]
+fn merge_and_get_returns_the_merged_value() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("db")).unwrap();
+    assert_eq!(db.merge_and_get("k", "a").unwrap().unwrap().0, vec!["a"]);
+    assert_eq!(
+        db.merge_and_get("k", "b").unwrap().unwrap().0,
+        vec!["a", "b"]
+    );
+}
+
+#[test]
+fn debug_output_names_path_and_types() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(&p).unwrap();
+    let s = format!("{:?}", db);
+    assert!(
+        s.contains(p.to_str().unwrap()) && s.contains("Words") && s.contains("MergeableDB"),
+        "{}",
+        s
+    );
+    let it = format!("{:?}", db.db_iter());
+    assert!(
+        it.contains("DBIter") && it.contains("alloc::string::String"),
+        "{}",
+        it
+    );
+}
+
+#[test]
+fn hash_skip_list_memtable() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::builder()
+        .prefix_len(2)
+        .hash_skip_list_memtable(1024)
+        .open(dir.path().join("db"))
+        .unwrap();
+    for k in &["aa1", "aa2", "ab1", "ba1"] {
+        db.merge(k, *k).unwrap();
+    }
+    db.merge("aa1", "x").unwrap();
+    assert_eq!(
+        db.get("aa1").unwrap().unwrap().0,
+        vec!["aa1".to_string(), "x".into()]
+    );
+    let n = db.db_iter().count();
+    assert_eq!(n, 4);
+}
+
+#[test]
+fn log_level_and_log_dir() {
+    let dir = tempdir().unwrap();
+    let logs = dir.path().join("logs");
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::builder()
+        .log_level(rocksdb::LogLevel::Debug)
+        .log_dir(&logs)
+        .open(dir.path().join("db"))
+        .unwrap();
+    db.merge("a", "x").unwrap();
+    assert_eq!(db.get("a").unwrap().unwrap().0, vec!["x".to_string()]);
+    assert!(std::fs::read_dir(&logs).unwrap().count() > 0);
+}
+
+#[test]
+fn bottommost_zstd_compression() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: MergeableDB<&str, Words, &str> = MergeableDB::builder()
+            .bottommost_compression(rocksdb::DBCompressionType::Zstd)
+            .zstd_max_train_bytes(1 << 20)
+            .open(&p)
+            .unwrap();
+        for i in 0..2000 {
+            db.merge(&format!("key{:05}", i), "hello world value")
+                .unwrap();
+        }
+        db.flush().unwrap();
+    }
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::builder()
+        .bottommost_compression(rocksdb::DBCompressionType::Zstd)
+        .zstd_max_train_bytes(1 << 20)
+        .compact_on_open(true)
+        .open(&p)
+        .unwrap();
+    assert_eq!(
+        db.get("key01234").unwrap().unwrap().0,
+        vec!["hello world value".to_string()]
+    );
+    assert_eq!(db.db_iter().count(), 2000);
+}
+
+#[test]
+fn merges_apply_in_write_order() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: MergeableDB<&str, Last, &str> = MergeableDB::new(&p).unwrap();
+        db.put("k", "base").unwrap();
+        for i in 0..50 {
+            db.merge("k", format!("v{}", i).as_str()).unwrap();
+            db.merge("j", format!("v{}", i).as_str()).unwrap();
+            if i % 7 == 0 {
+                db.flush().unwrap();
+            }
+        }
+        assert_eq!(db.get("k").unwrap().unwrap().0, "v49");
+        assert_eq!(db.get("j").unwrap().unwrap().0, "v49");
+    }
+    let db = MergeableDB::<&str, Last, &str>::builder()
+        .compact_on_open(true)
+        .open(&p)
+        .unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap().0, "v49");
+    assert_eq!(db.get("j").unwrap().unwrap().0, "v49");
+}
+
+#[test]
+fn merge_error_count_counts_corrupt_operands() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::new(dir.path().join("db")).unwrap();
+    db.merge("k", "a").unwrap();
+    db.merge("k", "!s").unwrap();
+    db.merge("k", "b").unwrap();
+    assert_eq!(db.merge_error_count(), 0);
+    assert_eq!(
+        db.get("k").unwrap().unwrap().0,
+        vec!["a".to_string(), "b".into()]
+    );
+    assert_eq!(db.merge_error_count(), 1);
+    db.merge("j", "!s").unwrap();
+    let _ = db.get("j");
+    assert_eq!(db.merge_error_count(), 2);
+}
+
+#[test]
+fn rate_limited_flushes() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::builder()
+        .rate_limit_bytes_per_sec(8 << 20)
+        .open(dir.path().join("db"))
+        .unwrap();
+    for i in 0..2000 {
+        db.merge(&format!("k{}", i), "some value here").unwrap();
+    }
+    db.flush().unwrap();
+    assert_eq!(db.db_iter().count(), 2000);
+}
+
+#[test]
+fn universal_compaction() {
+    let dir = tempdir().unwrap();
+    let mut uco = rocksdb::UniversalCompactOptions::default();
+    uco.set_size_ratio(2);
+    uco.set_min_merge_width(2);
+    let db: MergeableDB<&str, Words, &str> = MergeableDB::builder()
+        .compaction_style(rocksdb::DBCompactionStyle::Universal)
+        .universal_compaction_options(&uco)
+        .open(dir.path().join("db"))
+        .unwrap();
+    for round in 0..6 {
+        for i in 0..200 {
+            db.merge(&format!("k{}", i), &format!("r{}", round))
+                .unwrap();
+        }
+        db.flush().unwrap();
+    }
+    assert_eq!(db.db_iter().count(), 200);
+    assert_eq!(db.get("k5").unwrap().unwrap().0.len(), 6);
+}
+
+#[test]
+fn block_size_and_bloom_filter() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: MergeableDB<&str, Words, &str> = MergeableDB::builder()
+            .block_size(16 * 1024)
+            .bloom_filter_bits(10.0)
+            .open(&p)
+            .unwrap();
+        for i in 0..500 {
+            db.merge(&format!("k{}", i), "a").unwrap();
+            db.merge(&format!("k{}", i), "b").unwrap();
+        }
+        db.flush().unwrap();
+        assert_eq!(db.get("k7").unwrap().unwrap().0, vec!["a", "b"]);
+        assert!(db.get("nope").unwrap().is_none());
+    }
+    let opts_file = std::fs::read_dir(&p)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .filter(|n| n.starts_with("OPTIONS-"))
+        .max()
+        .unwrap();
+    let text = std::fs::read_to_string(p.join(opts_file)).unwrap();
+    assert!(text.contains("block_size=16384"), "{}", text);
+    assert!(text.contains("filter_policy=bloomfilter"), "{}", text);
+}
+
+#[test]
+fn periodic_compaction_and_sst_ttl() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: MergeableDB<&str, Words, &str> = MergeableDB::builder()
+            .periodic_compaction_seconds(3600)
+            .sst_ttl_seconds(7200)
+            .open(&p)
+            .unwrap();
+        db.merge("a", "x").unwrap();
+        db.merge("a", "y").unwrap();
+        db.flush().unwrap();
+        assert_eq!(db.get("a").unwrap().unwrap().0, vec!["x", "y"]);
+    }
+    let opts_file = std::fs::read_dir(&p)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .filter(|n| n.starts_with("OPTIONS-"))
+        .max()
+        .unwrap();
+    let text = std::fs::read_to_string(p.join(opts_file)).unwrap();
+    assert!(
+        text.contains("periodic_compaction_seconds=3600"),
+        "{}",
+        text
+    );
+    assert!(text.contains("\n  ttl=7200"), "{}", text);
+}
+
+#[test]
+fn wal_dir_holds_the_log() {
+    let dir = tempdir().unwrap();
+    let wal = dir.path().join("wal").join("nested");
+    let db: MergeableDB<u32, Sum, u64> = MergeableDB::builder()
+        .wal_dir(&wal)
+        .open(dir.path().join("db"))
+        .unwrap();
+    db.merge(1, 5).unwrap();
+    let logs: Vec<_> = std::fs::read_dir(&wal)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert!(logs.iter().any(|n| n.ends_with(".log")), "{:?}", logs);
+    assert!(!std::fs::read_dir(dir.path().join("db")).unwrap().any(|e| e
+        .unwrap()
+        .file_name()
+        .to_str()
+        .unwrap()
+        .ends_with(".log")));
+    drop(db);
+    let db: MergeableDB<u32, Sum, u64> = MergeableDB::builder()
+        .wal_dir(&wal)
+        .open(dir.path().join("db"))
+        .unwrap();
+    assert_eq!(db.get(1).unwrap().map(|c| c.0), Some(5));
+}
@@ -0,0 +1,401 @@
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[test]
+fn merged_string_lists_round_trip() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, SortedStringList, &SortedStringList> =
+        MergeableDB::new(dir.path().join("db")).unwrap();
+    let evens: SortedStringList = (0..2_000).step_by(2).map(|i| format!("{:05}", i)).collect();
+    db.put("k", &evens).unwrap();
+    for i in (1..2_000).step_by(2) {
+        db.merge("k", &SortedStringList::single(format!("{:05}", i)))
+            .unwrap();
+    }
+    let got = db.get("k").unwrap().unwrap();
+    assert_eq!(got.len(), 2_000);
+    assert!(got.iter().zip(0..).all(|(s, i)| s == format!("{:05}", i)));
+    db.flush().unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap(), got);
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+struct USet(std::collections::BTreeSet<u32>);
+impl Operate for USet {
+    type Item = u32;
+    fn add(&mut self, i: u32) {
+        self.0.insert(i);
+    }
+    fn remove(&mut self, i: u32) {
+        self.0.remove(&i);
+    }
+}
+
+#[test]
+fn operations_apply_in_order() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: MergeableDB<&str, Operations<USet>, &Operations<USet>> = MergeableDB::new(&p).unwrap();
+    db.merge("k", &Operations::add(1)).unwrap();
+    db.merge("k", &Operations::add(2)).unwrap();
+    db.merge("k", &Operations::add(3)).unwrap();
+    db.merge("k", &Operations::remove(2)).unwrap();
+    let v = db.get("k").unwrap().unwrap().value();
+    assert_eq!(v.0.into_iter().collect::<Vec<_>>(), vec![1, 3]);
+    db.merge(
+        "k",
+        &Operations::replace(USet(std::iter::once(9).collect())),
+    )
+    .unwrap();
+    db.merge("k", &Operations::add(4)).unwrap();
+    db.flush().unwrap();
+    db.merge("k", &Operations::remove(9)).unwrap();
+    let v = db.get("k").unwrap().unwrap().value();
+    assert_eq!(v.0.into_iter().collect::<Vec<_>>(), vec![4]);
+}
+
+#[test]
+fn operations_collapse_when_compacted() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: MergeableDB<&str, Operations<USet>, &Operations<USet>> =
+            MergeableDB::new(&p).unwrap();
+        for i in 0..2_000u32 {
+            db.merge("k", &Operations::add(i % 8)).unwrap();
+            db.merge("k", &Operations::remove((i + 4) % 8)).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    let db: MergeableDB<&str, Operations<USet>, &Operations<USet>> = MergeableDBBuilder::new()
+        .compact_on_open(true)
+        .open(&p)
+        .unwrap();
+    let stored = db.with_value("k", <[u8]>::len).unwrap().unwrap();
+    assert!(stored < 100, "{} bytes stored", stored);
+    let v = db.get("k").unwrap().unwrap().value();
+    assert_eq!(v.0.into_iter().collect::<Vec<_>>(), vec![4, 5, 6, 7]);
+}
+
+#[test]
+fn concurrent_counter_increments() {
+    let dir = tempdir().unwrap();
+    let db: CounterDB<&str> = CounterDB::new(dir.path().join("db")).unwrap();
+    std::thread::scope(|s| {
+        for t in 0..8u64 {
+            let db = &db;
+            s.spawn(move || {
+                for _ in 0..100 {
+                    db.incr("hits", t).unwrap();
+                    db.incr("n", 1).unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(db.get_count("hits").unwrap(), 2800);
+    assert_eq!(db.get_count("n").unwrap(), 800);
+    assert_eq!(db.get_count("none").unwrap(), 0);
+    let all: Vec<(String, u64)> = db.counts().map(Result::unwrap).collect();
+    assert_eq!(all, vec![("hits".into(), 2800), ("n".into(), 800)]);
+    db.reset("n").unwrap();
+    assert_eq!(db.get_count("n").unwrap(), 0);
+}
+
+#[test]
+fn bounded_set_keeps_the_largest() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: MergeableDB<&str, BoundedSet<u32, 3>, &BoundedSet<u32, 3>> =
+            MergeableDB::new(&p).unwrap();
+        for i in [5u32, 1, 9, 3, 7, 9, 2] {
+            db.merge("k", &BoundedSet::single(i)).unwrap();
+            if i == 9 {
+                db.flush().unwrap();
+            }
+        }
+        let v: Vec<u32> = db.get("k").unwrap().unwrap().iter().copied().collect();
+        assert_eq!(v, vec![5, 7, 9]);
+    }
+    {
+        let db = MergeableDB::<&str, BoundedSet<u32, 3>, &BoundedSet<u32, 3>>::builder()
+            .compact_on_open(true)
+            .open(&p)
+            .unwrap();
+        let v: Vec<u32> = db
+            .get("k")
+            .unwrap()
+            .unwrap()
+            .into_inner()
+            .into_iter()
+            .collect();
+        assert_eq!(v, vec![5, 7, 9]);
+    }
+    let s: BoundedSet<u32, 2> = (0..10).collect();
+    assert_eq!(s.len(), 2);
+}
+
+#[test]
+fn oversized_stored_sets_are_trimmed_on_read() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let wide: MergeableDB<&str, BoundedSet<u32, 10>, &BoundedSet<u32, 10>> =
+            MergeableDB::new(&p).unwrap();
+        wide.put("k", &(0..10).collect()).unwrap();
+        wide.put("merged", &(0..10).collect()).unwrap();
+    }
+    let db: MergeableDB<&str, BoundedSet<u32, 3>, &BoundedSet<u32, 3>> =
+        MergeableDB::new(&p).unwrap();
+    let v: Vec<u32> = db.get("k").unwrap().unwrap().iter().copied().collect();
+    assert_eq!(v, vec![7, 8, 9]);
+    db.merge("merged", &BoundedSet::single(4)).unwrap();
+    let v: Vec<u32> = db.get("merged").unwrap().unwrap().iter().copied().collect();
+    assert_eq!(v, vec![7, 8, 9]);
+    let bytes = (&(0..10).collect::<BoundedSet<u32, 10>>())
+        .try_serialize()
+        .unwrap();
+    assert_eq!(BoundedSet::<u32, 3>::deserialize(&bytes).unwrap().len(), 3);
+    let json: BoundedSet<u32, 2> = serde_json::from_str("[1, 5, 3]").unwrap();
+    assert_eq!(
+        json.into_inner().into_iter().collect::<Vec<_>>(),
+        vec![3, 5]
+    );
+}
+
+#[test]
+fn min_max_tracks_extremes() {
+    let dir = tempdir().unwrap();
+    let db: MinMaxDB<&str> = MinMaxDB::new(dir.path().join("db")).unwrap();
+    assert_eq!(db.get_min_max("t").unwrap(), None);
+    for v in [5i64, -3, 12, 0, 7, -3, 11] {
+        db.observe("t", v).unwrap();
+    }
+    db.observe("u", 4).unwrap();
+    assert_eq!(db.get_min_max("t").unwrap(), Some((-3, 12)));
+    assert_eq!(db.get_min_max("u").unwrap(), Some((4, 4)));
+    db.reset("u").unwrap();
+    assert_eq!(db.get_min_max("u").unwrap(), None);
+}
+
+#[test]
+fn sorted_string_list_merges_by_union() {
+    let dir = tempdir().unwrap();
+    let db: MergeableDB<&str, SortedStringList, &SortedStringList> =
+        MergeableDB::new(dir.path().join("db")).unwrap();
+    db.merge("k", &["pear", "apple", "fig"].iter().copied().collect())
+        .unwrap();
+    db.merge("k", &["fig", "banana", "apple"].iter().copied().collect())
+        .unwrap();
+    db.merge("k", &SortedStringList::single("zucchini"))
+        .unwrap();
+    let got = db.get("k").unwrap().unwrap();
+    assert_eq!(
+        got.iter().collect::<Vec<_>>(),
+        vec!["apple", "banana", "fig", "pear", "zucchini"]
+    );
+    assert!(got.contains("fig") && !got.contains("kiwi"));
+    let mut l = SortedStringList::new();
+    l.push("b");
+    l.push("a");
+    l.push("b");
+    assert_eq!(l.into_vec(), vec!["a".to_string(), "b".into()]);
+    assert!(SortedStringList::deserialize(b"\xa1b\xa1a").is_err());
+    assert!(SortedStringList::deserialize(b"\xa5ab").is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_merge_patches_apply_in_order() {
+    use serde_json::json;
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let patches = [
+        json!({"a": {"b": 1, "c": 2}, "d": [1, 2], "n": null}),
+        json!({"a": {"c": null, "e": {"f": true}}, "d": [3]}),
+        json!({"x": 5, "a": {"b": null}}),
+        json!({"x": {"y": null, "z": 1}, "q": null, "d": null}),
+    ];
+    {
+        let db: MergeableDB<&str, JsonMerge, &JsonMerge> = MergeableDB::new(&p).unwrap();
+        db.put("doc", &JsonMerge::new(json!({"q": 1, "keep": "me"})))
+            .unwrap();
+        for patch in &patches {
+            db.merge("doc", &JsonMerge::new(patch.clone())).unwrap();
+            db.merge("bare", &JsonMerge::new(patch.clone())).unwrap();
+            db.flush().unwrap();
+        }
+        let expected = json!({"keep": "me", "a": {"e": {"f": true}}, "x": {"z": 1}});
+        assert_eq!(db.get("doc").unwrap().unwrap().into_value(), expected);
+    }
+    let db: MergeableDB<&str, JsonMerge, &JsonMerge> = MergeableDB::builder()
+        .compact_on_open(true)
+        .open(&p)
+        .unwrap();
+    assert_eq!(
+        db.get("doc").unwrap().unwrap().into_value(),
+        json!({"keep": "me", "a": {"e": {"f": true}}, "x": {"z": 1}})
+    );
+    assert_eq!(
+        db.get("bare").unwrap().unwrap().into_value(),
+        json!({"a": {"e": {"f": true}}, "x": {"z": 1}, "n": null})
+    );
+    db.merge("s", &JsonMerge::new(json!(3))).unwrap();
+    db.merge("s", &JsonMerge::new(json!({"k": null, "j": 1})))
+        .unwrap();
+    assert_eq!(db.get("s").unwrap().unwrap().into_value(), json!({"j": 1}));
+}
+
+#[test]
+fn histogram_adds_bucket_counts() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: HistogramDB<&str> = HistogramDB::new(&p).unwrap();
+        assert!(db.get_histogram("lat").unwrap().is_empty());
+        db.add("lat", 10, 1).unwrap();
+        db.add("lat", -5, 2).unwrap();
+        db.add("lat", 10, 4).unwrap();
+        db.add("other", 1, 1).unwrap();
+    }
+    let db: HistogramDB<&str> = HistogramDB::new(&p).unwrap();
+    db.add("lat", 100, u64::MAX).unwrap();
+    db.add("lat", 100, 3).unwrap();
+    db.add("lat", -5, 1).unwrap();
+    let h = db.get_histogram("lat").unwrap();
+    assert_eq!(
+        h.into_iter().collect::<Vec<_>>(),
+        vec![(-5, 3), (10, 5), (100, u64::MAX)]
+    );
+    db.reset("lat").unwrap();
+    assert!(db.get_histogram("lat").unwrap().is_empty());
+    assert_eq!(db.get_histogram("other").unwrap().len(), 1);
+}
+
+#[test]
+fn replace_merge_keeps_the_last_value() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: MergeableDB<&str, ReplaceMerge<String>, &str> = MergeableDB::new(&p).unwrap();
+        db.merge("a", "1").unwrap();
+        assert_eq!(db.get("a").unwrap().unwrap().as_str(), "1");
+        db.put("b", "base").unwrap();
+        for i in 0..10 {
+            db.merge("a", &format!("v{}", i)).unwrap();
+            db.merge("c", &format!("c{}", i)).unwrap();
+            if i % 3 == 0 {
+                db.flush().unwrap();
+            }
+        }
+        db.merge("b", "new").unwrap();
+        assert_eq!(db.get("a").unwrap().unwrap().into_inner(), "v9");
+        assert_eq!(*db.get("b").unwrap().unwrap(), "new");
+    }
+    // Compacting on open combines operands with no base value, partially.
+    let db: MergeableDB<&str, ReplaceMerge<String>, &str> = MergeableDB::builder()
+        .compact_on_open(true)
+        .open(&p)
+        .unwrap();
+    assert_eq!(db.get("a").unwrap().unwrap().into_inner(), "v9");
+    assert_eq!(db.get("c").unwrap().unwrap().into_inner(), "c9");
+    db.merge("c", "after").unwrap();
+    assert_eq!(db.get("c").unwrap().unwrap().into_inner(), "after");
+    let raw: MergeableDB<&str, ReplaceMerge<u64>, u64> =
+        MergeableDB::new(dir.path().join("numbers")).unwrap();
+    raw.merge("n", 5).unwrap();
+    raw.merge("n", 7).unwrap();
+    assert_eq!(*raw.get("n").unwrap().unwrap(), 7);
+}
+
+#[derive(Debug, PartialEq)]
+struct Tally(u64);
+impl Serializable for &Tally {
+    type Bytes = [u8; 8];
+    fn serialize(self) -> [u8; 8] {
+        self.0.to_be_bytes()
+    }
+}
+impl Deserializable for Tally {
+    type Error = LengthError;
+    fn deserialize(b: &[u8]) -> Result<Self, LengthError> {
+        u64::deserialize(b).map(Tally)
+    }
+}
+impl VersionedValue for Tally {
+    fn current_version() -> u8 {
+        2
+    }
+    fn migrate(from: u8, b: &[u8]) -> Result<Self, LengthError> {
+        match from {
+            1 => u32::deserialize(b).map(|n| Tally(n as u64)),
+            _ => Err(LengthError {
+                expected: 0,
+                found: b.len(),
+            }),
+        }
+    }
+}
+impl AssociateMergeable for Tally {
+    fn merge(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+    fn into_bytes(self) -> Vec<u8> {
+        (&self).serialize().to_vec()
+    }
+    fn handle_deser_error(_: &[u8], _: &[u8], _: LengthError) -> MergePolicy<Self> {
+        MergePolicy::Skip
+    }
+}
+
+#[test]
+fn versioned_values_migrate_old_versions() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let raw: MergeableDB<&str, Versioned<Tally>, &[u8]> = MergeableDB::new(&p).unwrap();
+        raw.merge("k", &[1, 0, 0, 0, 5][..]).unwrap();
+        raw.put("old", &[1, 0, 0, 0, 9][..]).unwrap();
+        raw.merge("k", &[3, 0][..]).unwrap();
+        raw.merge("k", &[][..]).unwrap();
+        raw.flush().unwrap();
+    }
+    let db: MergeableDB<&str, Versioned<Tally>, &Versioned<Tally>> = MergeableDB::new(&p).unwrap();
+    assert_eq!(db.get("old").unwrap().unwrap().0, Tally(9));
+    db.merge("k", &Versioned(Tally(100))).unwrap();
+    assert_eq!(db.get("k").unwrap().unwrap().0, Tally(105));
+    db.merge("old", &Versioned(Tally(1))).unwrap();
+    assert_eq!(db.get("old").unwrap().unwrap().0, Tally(10));
+    assert!(matches!(
+        Versioned::<Tally>::deserialize(&[]),
+        Err(VersionedError::Untagged)
+    ));
+    assert_eq!(
+        (&Versioned(Tally(1))).try_serialize().unwrap(),
+        vec![2, 0, 0, 0, 0, 0, 0, 0, 1]
+    );
+}
+
+#[test]
+fn field_map_last_write_wins_per_field() {
+    use std::collections::HashMap;
+    let dir = tempdir().unwrap();
+    let db: FieldMapDB<&str, String, u64> = FieldMapDB::new(dir.path().join("db")).unwrap();
+    db.set_field("a", "x".to_owned(), 1).unwrap();
+    db.set_field("a", "y".to_owned(), 2).unwrap();
+    db.set_field("a", "x".to_owned(), 3).unwrap();
+    db.set_field("b", "z".to_owned(), 9).unwrap();
+    let expect: HashMap<String, u64> = vec![("x".to_owned(), 3), ("y".to_owned(), 2)]
+        .into_iter()
+        .collect();
+    assert_eq!(db.get_map("a").unwrap(), expect);
+    assert_eq!(db.get_map("b").unwrap().len(), 1);
+    assert!(db.get_map("c").unwrap().is_empty());
+    db.clear("a").unwrap();
+    db.set_field("a", "w".to_owned(), 0).unwrap();
+    assert_eq!(
+        db.get_map("a").unwrap().into_iter().collect::<Vec<_>>(),
+        vec![("w".to_owned(), 0)]
+    );
+    assert!(FieldMap::<String, u64>::deserialize(&[0xc4, 5, 1]).is_err());
+}
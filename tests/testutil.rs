@@ -0,0 +1,26 @@
+#![cfg(feature = "test-util")]
+
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[test]
+fn hammer_key_value_db() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<u64, u64, u64> = KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    rustyrocks::testutil::hammer(&db, 4, 2000);
+}
+
+#[test]
+fn hammered_contents_survive_a_reopen() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let before: Vec<(u64, u64)> = {
+        let db: KeyValueDB<u64, u64, u64> = KeyValueDB::open_default(&p).unwrap();
+        rustyrocks::testutil::hammer(&db, 8, 500);
+        db.db_iter().collect::<Result<_, _>>().unwrap()
+    };
+    assert!(!before.is_empty());
+    let db: KeyValueDB<u64, u64, u64> = KeyValueDB::open_default(&p).unwrap();
+    let after: Vec<(u64, u64)> = db.db_iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(before, after);
+}
@@ -0,0 +1,92 @@
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[test]
+fn conflicting_transactions_time_out() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let mut txo = rocksdb::TransactionDBOptions::default();
+    txo.set_txn_lock_timeout(10);
+    let mut o = rocksdb::Options::default();
+    o.create_if_missing(true);
+    let db: TransactionalDB<u32, String, &str> =
+        TransactionalDB::open_with_opts(&p, o, txo).unwrap();
+    let a = db.transaction();
+    let b = db.transaction();
+    a.put(1, "a").unwrap();
+    let err = b.put(1, "b").unwrap_err();
+    assert!(matches!(err, RustyRocksError::TimedOut), "{}", err);
+    a.commit().unwrap();
+    drop(b);
+    assert_eq!(db.get(1).unwrap().unwrap(), "a");
+    let c = db.transaction();
+    assert_eq!(c.get_for_update(1, true).unwrap().unwrap(), "a");
+    c.delete(1).unwrap();
+    c.rollback().unwrap();
+    assert_eq!(db.get(1).unwrap().unwrap(), "a");
+}
+
+#[test]
+fn owned_key_type_defaults_like_key_value_db() {
+    let dir = tempdir().unwrap();
+    let db: TransactionalDB<&str, String, &str, String> =
+        TransactionalDB::open_default(dir.path().join("db")).unwrap();
+    let txn: Transaction<'_, &str, String, &str, String> = db.transaction();
+    txn.put("k", "v").unwrap();
+    txn.commit().unwrap();
+    let same: &TransactionalDB<&str, String, &str> = &db;
+    assert_eq!(same.get("k").unwrap().as_deref(), Some("v"));
+}
+
+#[test]
+fn lock_timeout_is_applied() {
+    let dir = tempdir().unwrap();
+    let mut db: TransactionalDB<&str, String, &str> =
+        TransactionalDB::open_default(dir.path().join("db")).unwrap();
+    db.set_lock_timeout(std::time::Duration::from_millis(50));
+    let t1 = db.transaction();
+    t1.put("k", "1").unwrap();
+    let t2 = db.transaction();
+    let start = std::time::Instant::now();
+    assert!(matches!(t2.put("k", "2"), Err(RustyRocksError::TimedOut)));
+    assert!(matches!(
+        t2.get_for_update("k", true),
+        Err(RustyRocksError::TimedOut)
+    ));
+    assert!(start.elapsed() < std::time::Duration::from_millis(900));
+    t1.commit().unwrap();
+}
+
+#[test]
+fn exclusive_and_shared_locks() {
+    let dir = tempdir().unwrap();
+    let mut db: TransactionalDB<&str, String, &str> =
+        TransactionalDB::open_default(dir.path().join("db")).unwrap();
+    db.set_lock_timeout(std::time::Duration::from_millis(20));
+    {
+        let t = db.transaction();
+        t.put("k", "0").unwrap();
+        t.commit().unwrap();
+    }
+    // Exclusive: the second cannot lock at all.
+    let t1 = db.transaction();
+    let t2 = db.transaction();
+    assert_eq!(t1.get_for_update("k", true).unwrap().as_deref(), Some("0"));
+    assert!(matches!(
+        t2.get_for_update("k", true),
+        Err(RustyRocksError::TimedOut)
+    ));
+    t1.put("k", "1").unwrap();
+    t1.commit().unwrap();
+    drop(t2);
+    // Shared: both may read, but neither may then write.
+    let t1 = db.transaction();
+    let t2 = db.transaction();
+    assert_eq!(t1.get_for_update("k", false).unwrap().as_deref(), Some("1"));
+    assert_eq!(t2.get_for_update("k", false).unwrap().as_deref(), Some("1"));
+    assert!(matches!(t1.put("k", "2"), Err(RustyRocksError::TimedOut)));
+    t2.rollback().unwrap();
+    t1.put("k", "2").unwrap();
+    t1.commit().unwrap();
+    assert_eq!(db.get("k").unwrap().as_deref(), Some("2"));
+}
@@ -0,0 +1,290 @@
+use rustyrocks::*;
+use tempfile::tempdir;
+
+#[test]
+fn bulk_loader_writes_in_batches() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    let mut l = db.bulk_loader(1000).unwrap();
+    l.set_disable_wal(true);
+    l.set_compact_on_finish(true);
+    for k in 0..100_000u32 {
+        l.put(k, "v").unwrap();
+    }
+    l.put(100_000, "v").unwrap();
+    assert_eq!(l.finish().unwrap(), 100_001);
+    assert_eq!(db.db_keys().count(), 100_001);
+}
+
+#[test]
+fn ttl_db_expires_values_on_compaction() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: TtlDB<&str, String, &str> = TtlDB::new(&p, std::time::Duration::from_secs(1)).unwrap();
+    db.put("a", "b").unwrap();
+    assert_eq!(db.get("a").unwrap().unwrap(), "b");
+    std::thread::sleep(std::time::Duration::from_millis(2100));
+    db.compact_all();
+    assert!(db.get("a").unwrap().is_none());
+    assert_eq!(db.db_iter().count(), 0);
+}
+
+#[test]
+fn ttl_db_rounds_sub_second_ttls_up() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: TtlDB<&str, String, &str> =
+        TtlDB::new(&p, std::time::Duration::from_millis(300)).unwrap();
+    db.put("a", "b").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(2100));
+    db.compact_all();
+    assert!(db.get("a").unwrap().is_none());
+}
+
+#[test]
+fn sst_writer_files_can_be_ingested() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let f = dir.path().join("data.sst");
+    let opts = rocksdb::Options::default();
+    let mut w: SstWriter<u32, &str> = SstWriter::create(&opts, &f).unwrap();
+    w.put(1, "a").unwrap();
+    w.put(2, "b").unwrap();
+    assert!(w.put(1, "c").is_err());
+    w.finish().unwrap();
+    let db: KeyValueDB<u32, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    db.put(2, "old").unwrap();
+    db.ingest_sst_files(&[&f]).unwrap();
+    let all: Vec<(u32, String)> = db.db_iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(all, vec![(1, "a".into()), (2, "b".into())]);
+}
+
+#[test]
+fn simple_db_round_trips_serde_types() {
+    let dir = tempdir().unwrap();
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct U {
+        name: String,
+        age: u32,
+    }
+    let db: SimpleDB<String, U> = SimpleDB::open_default(dir.path().join("db")).unwrap();
+    db.put(
+        &"ada".into(),
+        &U {
+            name: "Ada".into(),
+            age: 36,
+        },
+    )
+    .unwrap();
+    assert_eq!(db.get(&"ada".into()).unwrap().unwrap().age, 36);
+    let all: Vec<(String, U)> = db.db_iter().map(Result::unwrap).collect();
+    assert_eq!(all.len(), 1);
+    db.delete(&"ada".into()).unwrap();
+    assert!(db.get(&"ada".into()).unwrap().is_none());
+    #[cfg(feature = "json")]
+    {
+        let j: SimpleDB<u32, Vec<String>, JsonCodec> =
+            SimpleDB::open_default(dir.path().join("json")).unwrap();
+        j.put(&3, &vec!["x".into()]).unwrap();
+        assert_eq!(j.get(&3).unwrap().unwrap(), vec!["x".to_string()]);
+    }
+}
+
+#[test]
+fn append_log_numbers_entries_across_reopens() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let log: AppendLog<String, &str> = AppendLog::open(&p).unwrap();
+        assert_eq!(log.append("a").unwrap(), 0);
+        assert_eq!(log.append("b").unwrap(), 1);
+    }
+    let log: AppendLog<String, &str> = AppendLog::open(&p).unwrap();
+    for w in ["c", "d", "e", "f", "g", "h", "i", "j", "k"] {
+        log.append(w).unwrap();
+    }
+    let all: Vec<(u64, String)> = log.db_iter().map(Result::unwrap).collect();
+    assert_eq!(all.len(), 11);
+    assert_eq!(all[10], (10, "k".to_string()));
+    assert!(all.windows(2).all(|w| w[0].0 + 1 == w[1].0));
+    assert_eq!(log.get(2).unwrap().as_deref(), Some("c"));
+}
+
+#[test]
+fn append_log_reads_only_the_last_key() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let raw: KeyValueDB<u64, Vec<u8>, &[u8]> = KeyValueDB::open_default(&p).unwrap();
+        raw.put(4, &[0xff, 0xfe][..]).unwrap();
+    }
+    let log: AppendLog<String, &str> = AppendLog::open(&p).unwrap();
+    assert_eq!(log.append("a").unwrap(), 5);
+    assert_eq!(log.append("b").unwrap(), 6);
+
+    let p = dir.path().join("full");
+    {
+        let raw: KeyValueDB<u64, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        raw.put(u64::MAX - 1, "x").unwrap();
+    }
+    let log: AppendLog<String, &str> = AppendLog::open(&p).unwrap();
+    assert_eq!(log.append("y").unwrap(), u64::MAX);
+    assert!(matches!(log.append("z"), Err(RustyRocksError::LogFull)));
+    assert_eq!(log.get(u64::MAX).unwrap().as_deref(), Some("y"));
+}
+
+#[test]
+fn temporary_db_is_removed_on_drop() {
+    let db: TemporaryDB<&str, String, &str> = KeyValueDB::temporary().unwrap();
+    db.put("a", "1").unwrap();
+    assert_eq!(db.get("a").unwrap().as_deref(), Some("1"));
+    let path = db.path().to_path_buf();
+    assert!(path.exists());
+    let other: TemporaryDB<&str, String, &str> = KeyValueDB::temporary().unwrap();
+    assert_ne!(other.path(), path);
+    drop(db);
+    assert!(!path.exists());
+}
+
+#[test]
+fn updates_since_tails_the_wal() {
+    let dir = tempdir().unwrap();
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_default(dir.path().join("db")).unwrap();
+    db.put("first", "x").unwrap();
+    let start = db.latest_sequence_number();
+    db.put("a", "1").unwrap();
+    db.delete("first").unwrap();
+    db.put_sorted(vec![("b", "2"), ("c", "3")]).unwrap();
+    let got: Vec<(u64, Vec<Update<String, String>>)> = db
+        .updates_since(start)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(got.len(), 3);
+    assert_eq!(
+        got[0].1,
+        vec![Update::Put("a".to_string(), "1".to_string())]
+    );
+    assert_eq!(got[1].1, vec![Update::Delete("first".to_string())]);
+    assert_eq!(
+        got[2].1,
+        vec![
+            Update::Put("b".into(), "2".into()),
+            Update::Put("c".into(), "3".into())
+        ]
+    );
+    assert!(got[0].0 < got[1].0 && got[1].0 < got[2].0);
+    let all: Vec<_> = db.updates_since(0).unwrap().map(Result::unwrap).collect();
+    assert_eq!(all.len(), 3);
+}
+
+#[test]
+fn updates_since_counts_the_writes_it_cannot_decode() {
+    let dir = tempdir().unwrap();
+    let mut opts = rocksdb::Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    opts.set_merge_operator_associative("last", |_, existing, operands| {
+        operands.iter().last().or(existing).map(<[u8]>::to_vec)
+    });
+    let db: KeyValueDB<&str, String, &str> =
+        KeyValueDB::open_cf_with_opts(dir.path().join("db"), opts, ["other"]).unwrap();
+    db.put("first", "x").unwrap();
+    let start = db.latest_sequence_number();
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.put(b"a", b"1");
+    batch.merge(b"m", b"2");
+    batch.put(b"b", b"3");
+    db.write_raw(batch).unwrap();
+    db.write_batch_cf(vec![
+        ("default", BatchOp::Put("c", "4")),
+        ("other", BatchOp::Put("d", "5")),
+        ("default", BatchOp::Put("e", "6")),
+        ("default", BatchOp::Delete("a")),
+    ])
+    .unwrap();
+    db.put("f", "7").unwrap();
+    let got: Vec<Vec<Update<String, String>>> = db
+        .updates_since(start)
+        .unwrap()
+        .map(|batch| batch.unwrap().1)
+        .collect();
+    assert_eq!(
+        got,
+        vec![
+            vec![
+                Update::Put("a".into(), "1".into()),
+                Update::Put("b".into(), "3".into()),
+                Update::Undecoded(1),
+            ],
+            vec![Update::Put("c".into(), "4".into()), Update::Undecoded(3)],
+            vec![Update::Put("f".into(), "7".into())],
+        ]
+    );
+    assert_eq!(db.get("e").unwrap().as_deref(), Some("6"));
+}
+
+#[test]
+fn indexed_batch_reads_its_own_writes() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    {
+        let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+        db.put("old", "o").unwrap();
+        db.put("gone", "g").unwrap();
+        let mut batch = db.indexed_batch().unwrap();
+        assert!(batch.is_empty());
+        batch.put("new", "n").unwrap();
+        batch.delete("gone").unwrap();
+        assert_eq!(batch.get("new").unwrap().as_deref(), Some("n"));
+        assert_eq!(batch.get("old").unwrap().as_deref(), Some("o"));
+        assert_eq!(batch.get("gone").unwrap(), None);
+        assert_eq!(db.get("new").unwrap(), None);
+        assert_eq!(db.get("gone").unwrap().as_deref(), Some("g"));
+        batch.merge("m", "x").unwrap();
+        assert!(matches!(batch.get("m"), Err(RustyRocksError::StagedMerge)));
+        assert_eq!(batch.len(), 3);
+        // No merge operator: drop the merge by rebuilding.
+        let mut batch = db.indexed_batch().unwrap();
+        batch.put("new", "n").unwrap();
+        batch.delete("gone").unwrap();
+        db.write_indexed(batch).unwrap();
+    }
+    let db: KeyValueDB<&str, String, &str> = KeyValueDB::open_default(&p).unwrap();
+    assert_eq!(db.get("new").unwrap().as_deref(), Some("n"));
+    assert_eq!(db.get("gone").unwrap(), None);
+}
+
+#[test]
+fn last_modified_timestamps_increase() {
+    let dir = tempdir().unwrap();
+    let p = dir.path().join("db");
+    let db: LastModifiedDB<&str, String, &str> = LastModifiedDB::new(&p).unwrap();
+    assert_eq!(db.last_modified("a").unwrap(), None);
+    db.put("a", "1").unwrap();
+    let t1 = db.last_modified("a").unwrap().unwrap();
+    db.put("a", "2").unwrap();
+    let t2 = db.last_modified("a").unwrap().unwrap();
+    assert!(t2 > t1);
+    assert_eq!(db.get("a").unwrap().as_deref(), Some("2"));
+    assert_eq!(db.db_iter().count(), 1);
+    db.delete("a").unwrap();
+    assert_eq!(db.last_modified("a").unwrap(), None);
+    drop(db);
+    let mut opts = rocksdb::Options::default();
+    opts.set_merge_operator_associative("concat", |_, existing, ops| {
+        let mut v = existing.map(|e| e.to_vec()).unwrap_or_default();
+        for op in ops {
+            v.extend_from_slice(op);
+        }
+        Some(v)
+    });
+    let db: LastModifiedDB<&str, String, &str> = LastModifiedDB::open_with_opts(&p, opts).unwrap();
+    db.merge("m", "x").unwrap();
+    let t3 = db.last_modified("m").unwrap().unwrap();
+    db.merge("m", "y").unwrap();
+    assert!(db.last_modified("m").unwrap().unwrap() > t3);
+    assert_eq!(db.get("m").unwrap().as_deref(), Some("xy"));
+}